@@ -0,0 +1,55 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use oxide_arbiter::{CreateOrderRequest, OrderBookService, OrderSide, OrderType, TimeInForce};
+use rust_decimal::Decimal;
+use std::hint::black_box;
+use uuid::Uuid;
+
+fn populated_book(item_id: Uuid, resting_orders: usize) -> OrderBookService {
+    let mut order_book = OrderBookService::new();
+    for i in 0..resting_orders {
+        let _ = order_book.add_order(CreateOrderRequest {
+            item_id,
+            user_id: Uuid::new_v4(),
+            order_side: OrderSide::Sell,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GTC,
+            price: Some(Decimal::from(100 + (i % 1_000) as i64)),
+            quantity: Decimal::from(1_000_000),
+            ..Default::default()
+        });
+    }
+    order_book
+}
+
+fn crossing_order_insertion(c: &mut Criterion) {
+    let mut group = c.benchmark_group("crossing_order_insertion");
+
+    for resting_orders in [100, 1_000, 10_000, 100_000] {
+        let item_id = Uuid::new_v4();
+        let mut order_book = populated_book(item_id, resting_orders);
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(resting_orders),
+            &resting_orders,
+            |b, _| {
+                b.iter(|| {
+                    black_box(order_book.add_order(CreateOrderRequest {
+                        item_id,
+                        user_id: Uuid::new_v4(),
+                        order_side: OrderSide::Buy,
+                        order_type: OrderType::Limit,
+                        time_in_force: TimeInForce::GTC,
+                        price: Some(Decimal::from(100)),
+                        quantity: Decimal::ONE,
+                        ..Default::default()
+                    }))
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, crossing_order_insertion);
+criterion_main!(benches);
@@ -10,8 +10,9 @@ fn main() {
         order_side: OrderSide::Buy,
         order_type: OrderType::Limit,
         time_in_force: TimeInForce::DAY,
-        price: Decimal::from_str("10.0").unwrap(),
+        price: Some(Decimal::from_str("10.0").unwrap()),
         quantity: Decimal::from_str("100.0").unwrap(),
+        ..Default::default()
     });
     let _ = order_book.add_order(CreateOrderRequest {
         item_id: uuid::Uuid::new_v4(),
@@ -19,22 +20,13 @@ fn main() {
         order_side: OrderSide::Sell,
         order_type: OrderType::Limit,
         time_in_force: TimeInForce::DAY,
-        price: Decimal::from_str("12.0").unwrap(),
+        price: Some(Decimal::from_str("12.0").unwrap()),
         quantity: Decimal::from_str("50.0").unwrap(),
+        ..Default::default()
     });
-    for (_, order_book_order) in order_book.get_orders() {
-        println!("--- Order Details ---");
-        println!("Order ID: {}", order_book_order.id);
-        println!("Item ID: {}", order_book_order.item_id);
-        println!("User ID: {}", order_book_order.user_id);
-        println!("Order Type: {:?}", order_book_order.order_type);
-        println!("Order quantity: {}", order_book_order.quantity);
-        println!("Order Status: {:?}", order_book_order.status);
-        println!("Order Created At: {}", order_book_order.created_at);
-        println!("Order Updated At: {}", order_book_order.updated_at);
-        println!("---------------------");
+    for order_book_order in order_book.get_orders().values() {
+        println!("{order_book_order}");
     }
     println!("OrderBookService created successfully.");
     println!("Hello, world!");
 }
-
@@ -8,16 +8,7 @@ fn print_orders(book: &OrderBookService) {
     let mut orders: Vec<_> = book.get_orders().values().collect();
     orders.sort_by_key(|o| o.created_at);
     for order in orders {
-        println!(
-            "  [{:?}] {:?} {:?} — {}/{} units @ {} — created {}",
-            order.status,
-            order.order_side,
-            order.order_type,
-            order.quantity_filled,
-            order.quantity,
-            order.price,
-            order.created_at.format("%H:%M:%S%.3f"),
-        );
+        println!("  {order}");
     }
 }
 
@@ -30,9 +21,10 @@ fn main() {
         user_id: uuid::Uuid::new_v4(),
         order_side: OrderSide::Sell,
         order_type: OrderType::Limit,
-        price: Decimal::from_str("50.0").unwrap(),
+        price: Some(Decimal::from_str("50.0").unwrap()),
         quantity: Decimal::from_str("100.0").unwrap(),
         time_in_force: TimeInForce::GTC,
+        ..Default::default()
     })
     .unwrap();
     book.add_order(CreateOrderRequest {
@@ -40,17 +32,15 @@ fn main() {
         user_id: uuid::Uuid::new_v4(),
         order_side: OrderSide::Buy,
         order_type: OrderType::Limit,
-        price: Decimal::from_str("50.0").unwrap(),
+        price: Some(Decimal::from_str("50.0").unwrap()),
         quantity: Decimal::from_str("100.0").unwrap(),
         time_in_force: TimeInForce::GTC,
+        ..Default::default()
     })
     .unwrap();
     println!("Trades produced:");
     for trade in &book.trades {
-        println!(
-            "  trade {} — {} units @ {}",
-            trade.id, trade.quantity, trade.price
-        );
+        println!("  {trade}");
     }
     println!("\nOrders:");
     print_orders(&book);
@@ -62,9 +52,10 @@ fn main() {
         user_id: uuid::Uuid::new_v4(),
         order_side: OrderSide::Buy,
         order_type: OrderType::Limit,
-        price: Decimal::from_str("30.0").unwrap(),
+        price: Some(Decimal::from_str("30.0").unwrap()),
         quantity: Decimal::from_str("200.0").unwrap(),
         time_in_force: TimeInForce::GTC,
+        ..Default::default()
     })
     .unwrap();
     // Sell fills only part of the resting buy — buy stays PartiallyFilled
@@ -73,17 +64,15 @@ fn main() {
         user_id: uuid::Uuid::new_v4(),
         order_side: OrderSide::Sell,
         order_type: OrderType::Limit,
-        price: Decimal::from_str("30.0").unwrap(),
+        price: Some(Decimal::from_str("30.0").unwrap()),
         quantity: Decimal::from_str("80.0").unwrap(),
         time_in_force: TimeInForce::GTC,
+        ..Default::default()
     })
     .unwrap();
     println!("Trades produced:");
     for trade in &book.trades {
-        println!(
-            "  trade {} — {} units @ {}",
-            trade.id, trade.quantity, trade.price
-        );
+        println!("  {trade}");
     }
     println!("\nOrders:");
     print_orders(&book);
@@ -125,5 +114,7 @@ fn main() {
         .filter(|o| matches!(o.status, OrderStatus::Open))
         .count();
     println!("Open: {open_count}");
-}
 
+    println!("\n=== Book Summary ===");
+    println!("{}", book.summary());
+}
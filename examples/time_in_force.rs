@@ -11,9 +11,10 @@ fn main() {
         user_id: uuid::Uuid::new_v4(),
         order_side: OrderSide::Sell,
         order_type: OrderType::Limit,
-        price: Decimal::from_str("10.0").unwrap(),
+        price: Some(Decimal::from_str("10.0").unwrap()),
         quantity: Decimal::from_str("30.0").unwrap(),
         time_in_force: TimeInForce::GTC,
+        ..Default::default()
     })
     .unwrap();
     // IOC buy for 100 — only 30 are available
@@ -23,9 +24,10 @@ fn main() {
             user_id: uuid::Uuid::new_v4(),
             order_side: OrderSide::Buy,
             order_type: OrderType::Limit,
-            price: Decimal::from_str("10.0").unwrap(),
+            price: Some(Decimal::from_str("10.0").unwrap()),
             quantity: Decimal::from_str("100.0").unwrap(),
             time_in_force: TimeInForce::IOC,
+            ..Default::default()
         })
         .unwrap();
     println!("IOC order status:        {:?}", ioc.status);
@@ -43,9 +45,10 @@ fn main() {
         user_id: uuid::Uuid::new_v4(),
         order_side: OrderSide::Sell,
         order_type: OrderType::Limit,
-        price: Decimal::from_str("20.0").unwrap(),
+        price: Some(Decimal::from_str("20.0").unwrap()),
         quantity: Decimal::from_str("100.0").unwrap(),
         time_in_force: TimeInForce::GTC,
+        ..Default::default()
     })
     .unwrap();
     // FOK buy at 10.0 — no price match, so zero trades → entire order cancelled
@@ -55,9 +58,10 @@ fn main() {
             user_id: uuid::Uuid::new_v4(),
             order_side: OrderSide::Buy,
             order_type: OrderType::Limit,
-            price: Decimal::from_str("10.0").unwrap(),
+            price: Some(Decimal::from_str("10.0").unwrap()),
             quantity: Decimal::from_str("50.0").unwrap(),
             time_in_force: TimeInForce::FOK,
+            ..Default::default()
         })
         .unwrap();
     println!("FOK order status:  {:?}", fok.status);
@@ -73,9 +77,10 @@ fn main() {
             user_id: uuid::Uuid::new_v4(),
             order_side: OrderSide::Buy,
             order_type: OrderType::Limit,
-            price: Decimal::from_str("25.0").unwrap(),
+            price: Some(Decimal::from_str("25.0").unwrap()),
             quantity: Decimal::from_str("50.0").unwrap(),
             time_in_force: TimeInForce::GTC,
+            ..Default::default()
         })
         .unwrap();
     println!("GTC order status after placement: {:?}", gtc.status);
@@ -86,4 +91,3 @@ fn main() {
         after_cancel.status
     );
 }
-
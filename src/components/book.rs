@@ -0,0 +1,145 @@
+use crate::components::dto::OrderSide;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use uuid::Uuid;
+
+/// One side of an item's book: FIFO queues of order ids keyed by price.
+pub(crate) type PriceLevels = BTreeMap<Decimal, VecDeque<Uuid>>;
+
+/// Resting price levels and untriggered stops of a single item.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ItemBook {
+    pub(crate) bids: PriceLevels,
+    pub(crate) asks: PriceLevels,
+    pub(crate) stops: Vec<Uuid>,
+}
+
+impl ItemBook {
+    pub(crate) fn side(&self, side: OrderSide) -> &PriceLevels {
+        match side {
+            OrderSide::Buy => &self.bids,
+            OrderSide::Sell => &self.asks,
+        }
+    }
+
+    pub(crate) fn side_mut(&mut self, side: OrderSide) -> &mut PriceLevels {
+        match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        }
+    }
+
+    pub(crate) fn has_levels(&self) -> bool {
+        !self.bids.is_empty() || !self.asks.is_empty()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        !self.has_levels() && self.stops.is_empty()
+    }
+}
+
+/// The item books an engine matches against. A multi-item engine keys them by
+/// item id and drops a book once it empties; a single-item engine holds its
+/// one book directly, so no lookup sits between it and the levels.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Books {
+    Multi(HashMap<Uuid, ItemBook>),
+    Single { item_id: Uuid, book: ItemBook },
+}
+
+impl Default for Books {
+    fn default() -> Self {
+        Books::Multi(HashMap::new())
+    }
+}
+
+impl Books {
+    pub(crate) fn single(item_id: Uuid) -> Self {
+        Books::Single {
+            item_id,
+            book: ItemBook::default(),
+        }
+    }
+
+    /// Whether orders for `item_id` can be placed here.
+    pub(crate) fn holds(&self, item_id: Uuid) -> bool {
+        match self {
+            Books::Multi(_) => true,
+            Books::Single {
+                item_id: book_item_id,
+                ..
+            } => *book_item_id == item_id,
+        }
+    }
+
+    pub(crate) fn get(&self, item_id: Uuid) -> Option<&ItemBook> {
+        match self {
+            Books::Multi(books) => books.get(&item_id),
+            Books::Single {
+                item_id: book_item_id,
+                book,
+            } => (*book_item_id == item_id).then_some(book),
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, item_id: Uuid) -> Option<&mut ItemBook> {
+        match self {
+            Books::Multi(books) => books.get_mut(&item_id),
+            Books::Single {
+                item_id: book_item_id,
+                book,
+            } => (*book_item_id == item_id).then_some(book),
+        }
+    }
+
+    /// The item's book, created on first use. A single-item engine only ever
+    /// receives its own item, since `prepare_order` rejects any other.
+    pub(crate) fn entry(&mut self, item_id: Uuid) -> &mut ItemBook {
+        match self {
+            Books::Multi(books) => books.entry(item_id).or_default(),
+            Books::Single {
+                item_id: book_item_id,
+                book,
+            } => {
+                debug_assert_eq!(*book_item_id, item_id, "order for a foreign item");
+                book
+            }
+        }
+    }
+
+    /// Drops the item's book from a multi-item engine once it holds nothing.
+    pub(crate) fn prune(&mut self, item_id: Uuid) {
+        if let Books::Multi(books) = self
+            && books.get(&item_id).is_some_and(ItemBook::is_empty)
+        {
+            books.remove(&item_id);
+        }
+    }
+
+    /// A copy holding only `item_id`'s book, for dry runs against one item.
+    pub(crate) fn only(&self, item_id: Uuid) -> Self {
+        match self {
+            Books::Multi(books) => Books::Multi(
+                books
+                    .get(&item_id)
+                    .map(|book| (item_id, book.clone()))
+                    .into_iter()
+                    .collect(),
+            ),
+            Books::Single { .. } => self.clone(),
+        }
+    }
+
+    pub(crate) fn iter(&self) -> Box<dyn Iterator<Item = (Uuid, &ItemBook)> + '_> {
+        match self {
+            Books::Multi(books) => Box::new(books.iter().map(|(item_id, book)| (*item_id, book))),
+            Books::Single { item_id, book } => Box::new(std::iter::once((*item_id, book))),
+        }
+    }
+
+    pub(crate) fn reserve(&mut self, items: usize) {
+        if let Books::Multi(books) = self {
+            books.reserve(items);
+        }
+    }
+}
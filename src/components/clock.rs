@@ -0,0 +1,51 @@
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use chrono::{DateTime, Duration, Utc};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A manually driven clock. Clones share the same time, so a test can keep a
+/// handle and advance the clock a book was built with.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl MockClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        MockClock {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, DateTime<Utc>> {
+        self.now
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.lock() = now;
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        *self.lock() += duration;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.lock()
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+
+use rust_decimal::{Decimal, RoundingStrategy};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SelfTradePrevention {
+    #[default]
+    Allow,
+    CancelResting,
+    CancelIncoming,
+    CancelBoth,
+}
+
+/// Price a crossing limit order trades at. `MakerPrice` passes any price
+/// improvement to the aggressor; `AggressorPrice` disables it by filling at the
+/// incoming limit. `Midpoint` rounds toward the resting price so it lands on
+/// the item's tick and `price_dp`. Market orders always take the resting price.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MatchPricePolicy {
+    #[default]
+    MakerPrice,
+    Midpoint,
+    AggressorPrice,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FeeSchedule {
+    pub maker_bps: Decimal,
+    pub taker_bps: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SizeLimits {
+    pub min_quantity: Option<Decimal>,
+    pub max_quantity: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PrecisionRounding {
+    #[default]
+    Reject,
+    MidpointNearestEven,
+    MidpointAwayFromZero,
+    ToZero,
+    AwayFromZero,
+}
+
+impl PrecisionRounding {
+    pub fn strategy(self) -> Option<RoundingStrategy> {
+        match self {
+            PrecisionRounding::Reject => None,
+            PrecisionRounding::MidpointNearestEven => Some(RoundingStrategy::MidpointNearestEven),
+            PrecisionRounding::MidpointAwayFromZero => Some(RoundingStrategy::MidpointAwayFromZero),
+            PrecisionRounding::ToZero => Some(RoundingStrategy::ToZero),
+            PrecisionRounding::AwayFromZero => Some(RoundingStrategy::AwayFromZero),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PrecisionConfig {
+    pub price_dp: u32,
+    pub size_dp: u32,
+    pub rounding: PrecisionRounding,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderBookConfig {
+    pub self_trade_prevention: SelfTradePrevention,
+    pub fee_schedule: FeeSchedule,
+    pub tick_sizes: HashMap<Uuid, Decimal>,
+    pub size_limits: HashMap<Uuid, SizeLimits>,
+    pub max_market_deviation: Decimal,
+    pub precision: Option<PrecisionConfig>,
+    pub allow_negative_prices: bool,
+    pub match_price_policy: MatchPricePolicy,
+    pub circuit_breaker: Option<Decimal>,
+    pub max_open_orders_per_user: Option<usize>,
+    pub max_trades: Option<usize>,
+    pub record_commands: bool,
+}
+
+impl Default for OrderBookConfig {
+    fn default() -> Self {
+        OrderBookConfig {
+            self_trade_prevention: SelfTradePrevention::default(),
+            fee_schedule: FeeSchedule::default(),
+            tick_sizes: HashMap::new(),
+            size_limits: HashMap::new(),
+            max_market_deviation: Decimal::new(5, 2),
+            precision: None,
+            allow_negative_prices: false,
+            match_price_policy: MatchPricePolicy::default(),
+            circuit_breaker: None,
+            max_open_orders_per_user: None,
+            max_trades: None,
+            record_commands: false,
+        }
+    }
+}
@@ -1,23 +1,33 @@
+use crate::components::config::OrderBookConfig;
+use crate::components::errors::OrderError;
 use chrono::{DateTime, Utc};
 use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::fmt;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(dead_code)]
 pub enum TimeInForce {
+    #[default]
     GTC, // Good Till Cancelled
-    IOC, // Immediate Or Cancel
-    FOK, // Fill Or Kill
-    DAY, // Day Order
+    IOC,                // Immediate Or Cancel
+    FOK,                // Fill Or Kill
+    DAY,                // Day Order
+    GTD(DateTime<Utc>), // Good Till Date
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum OrderSide {
+    #[default]
     Buy,
     Sell,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(dead_code)]
 pub enum OrderStatus {
     Open,
@@ -26,14 +36,24 @@ pub enum OrderStatus {
     Cancelled,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(dead_code)]
 pub enum OrderType {
+    #[default]
     Limit,
     Market,
+    Stop {
+        trigger: Decimal,
+    },
+    StopLimit {
+        trigger: Decimal,
+        limit: Decimal,
+    },
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(dead_code)]
 pub struct Order {
     pub id: Uuid,
@@ -45,13 +65,22 @@ pub struct Order {
     pub price: Decimal,
     pub quantity: Decimal,
     pub quantity_filled: Decimal,
+    pub fill_notional: Decimal,
+    pub display_quantity: Option<Decimal>,
+    pub visible_quantity: Decimal,
+    pub min_quantity: Option<Decimal>,
+    pub hidden: bool,
+    pub idempotency_key: Option<Uuid>,
+    pub price_protected: bool,
     pub status: OrderStatus,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub expires_at: Option<DateTime<Utc>>,
+    pub seq: u64,
 }
 
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(dead_code)]
 pub struct Trade {
     pub id: Uuid,
@@ -60,16 +89,266 @@ pub struct Trade {
     pub item_id: Uuid,
     pub quantity: Decimal,
     pub price: Decimal,
+    pub maker_fee: Decimal,
+    pub taker_fee: Decimal,
     pub timestamp: chrono::DateTime<Utc>,
+    pub seq: u64,
+    pub aggressor_seq: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Fill {
+    pub trade_id: Uuid,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// OHLC summary of the trades in one `[start, start + interval)` bucket.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Candle {
+    pub start: DateTime<Utc>,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal,
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Order {} [{:?}] {:?} {:?} {}/{} @ {} (item {})",
+            self.id,
+            self.status,
+            self.order_side,
+            self.order_type,
+            self.quantity_filled,
+            self.quantity,
+            self.price,
+            self.item_id
+        )
+    }
 }
 
+impl fmt::Display for Trade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Trade {} {} @ {} (item {}, buy {}, sell {})",
+            self.id, self.quantity, self.price, self.item_id, self.buy_order_id, self.sell_order_id
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(dead_code)]
 pub struct CreateOrderRequest {
     pub item_id: Uuid,
     pub user_id: Uuid,
     pub order_side: OrderSide,
     pub order_type: OrderType,
-    pub price: Decimal,
+    pub price: Option<Decimal>,
     pub quantity: Decimal,
     pub time_in_force: TimeInForce,
+    pub display_quantity: Option<Decimal>,
+    pub min_quantity: Option<Decimal>,
+    pub post_only: bool,
+    pub reduce_only: bool,
+    pub hidden: bool,
+    pub idempotency_key: Option<Uuid>,
+}
+
+impl CreateOrderRequest {
+    /// A GTC limit order. `price` and `quantity` accept anything `Decimal`
+    /// converts from fallibly, such as `&str` or `f64`.
+    pub fn limit<P, Q>(
+        item_id: Uuid,
+        user_id: Uuid,
+        order_side: OrderSide,
+        price: P,
+        quantity: Q,
+    ) -> Result<Self, rust_decimal::Error>
+    where
+        P: TryInto<Decimal, Error = rust_decimal::Error>,
+        Q: TryInto<Decimal, Error = rust_decimal::Error>,
+    {
+        Ok(CreateOrderRequest {
+            item_id,
+            user_id,
+            order_side,
+            order_type: OrderType::Limit,
+            price: Some(price.try_into()?),
+            quantity: quantity.try_into()?,
+            time_in_force: TimeInForce::GTC,
+            ..Default::default()
+        })
+    }
+
+    /// A market order without a protection price.
+    pub fn market<Q>(
+        item_id: Uuid,
+        user_id: Uuid,
+        order_side: OrderSide,
+        quantity: Q,
+    ) -> Result<Self, rust_decimal::Error>
+    where
+        Q: TryInto<Decimal, Error = rust_decimal::Error>,
+    {
+        Ok(CreateOrderRequest {
+            item_id,
+            user_id,
+            order_side,
+            order_type: OrderType::Market,
+            price: None,
+            quantity: quantity.try_into()?,
+            time_in_force: TimeInForce::GTC,
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BookDepth {
+    pub bids: Vec<(Decimal, Decimal)>,
+    pub asks: Vec<(Decimal, Decimal)>,
+}
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AuctionResult {
+    pub clearing_price: Decimal,
+    pub matched_volume: Decimal,
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BookStats {
+    pub open_orders: usize,
+    pub bid_levels: usize,
+    pub ask_levels: usize,
+    pub total_bid_qty: Decimal,
+    pub total_ask_qty: Decimal,
+    pub last_trade_price: Option<Decimal>,
+    pub trade_count: usize,
+}
+
+/// Running totals of an item's trades, updated as each trade is recorded so
+/// they keep counting trades evicted under `max_trades`.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TradedTotals {
+    pub volume: Decimal,
+    pub notional: Decimal,
+    pub trade_count: usize,
+    pub last_price: Option<Decimal>,
+}
+
+/// A user's net quantity in one item with its weighted-average cost and the
+/// P&L realized so far, updated as each of the user's trades is recorded.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PositionState {
+    pub quantity: Decimal,
+    pub average_cost: Decimal,
+    pub realized_pnl: Decimal,
+}
+
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderBookSnapshot {
+    pub config: OrderBookConfig,
+    pub orders: Vec<Order>,
+    pub trades: Vec<Trade>,
+    pub book_sequences: HashMap<Uuid, u64>,
+    pub evicted_trades: usize,
+    pub traded_totals: HashMap<Uuid, TradedTotals>,
+    /// Keyed by user id, then item id.
+    pub positions: HashMap<Uuid, HashMap<Uuid, PositionState>>,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PreviewResult {
+    pub status: Result<OrderStatus, OrderError>,
+    pub fills: Vec<(Decimal, Decimal)>,
+    pub quantity_filled: Decimal,
+    pub average_price: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImpactEstimate {
+    pub average_price: Option<Decimal>,
+    pub worst_price: Option<Decimal>,
+    pub quantity_filled: Decimal,
+    pub unfilled_quantity: Decimal,
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderOutcome {
+    pub order: Order,
+    pub trades: Vec<Trade>,
+}
+
+/// How a submission ended, read from the order's final status.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MatchResult {
+    FullyFilled,
+    PartiallyFilledResting,
+    /// Part of the order filled and the rest was cancelled, as for an IOC or
+    /// market order that ran out of liquidity.
+    PartiallyFilledCancelled,
+    Resting,
+    Cancelled,
+    Rejected(OrderError),
+}
+
+impl From<&Order> for MatchResult {
+    fn from(order: &Order) -> Self {
+        match order.status {
+            OrderStatus::Closed => MatchResult::FullyFilled,
+            OrderStatus::PartiallyFilled => MatchResult::PartiallyFilledResting,
+            OrderStatus::Open => MatchResult::Resting,
+            OrderStatus::Cancelled if order.quantity_filled > Decimal::ZERO => {
+                MatchResult::PartiallyFilledCancelled
+            }
+            OrderStatus::Cancelled => MatchResult::Cancelled,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OrderEvent {
+    Accepted(Order),
+    Rejected { reason: OrderError },
+    Traded(Trade),
+    Cancelled { order_id: Uuid },
+    Expired { order_id: Uuid },
+    StatusChanged { order_id: Uuid, status: OrderStatus },
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BookCommand {
+    Submit {
+        order_id: Uuid,
+        request: CreateOrderRequest,
+    },
+    Cancel {
+        order_id: Uuid,
+    },
+    Amend {
+        order_id: Uuid,
+        price: Option<Decimal>,
+        quantity: Option<Decimal>,
+    },
 }
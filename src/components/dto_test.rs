@@ -0,0 +1,220 @@
+#[cfg(test)]
+#[cfg(feature = "serde")]
+mod tests {
+    use crate::components::{
+        config::OrderBookConfig,
+        dto::{
+            BookCommand, CreateOrderRequest, OrderBookSnapshot, OrderSide, OrderStatus, OrderType,
+            TimeInForce, Trade,
+        },
+        errors::ReplayError,
+        services::OrderBookService,
+    };
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+    use uuid::Uuid;
+
+    #[test]
+    fn should_round_trip_create_order_request_through_json() {
+        let create_order_request = CreateOrderRequest {
+            item_id: Uuid::new_v4(),
+            user_id: Uuid::new_v4(),
+            order_side: OrderSide::Sell,
+            order_type: OrderType::StopLimit {
+                trigger: Decimal::from_str("9.5").unwrap(),
+                limit: Decimal::from_str("9.25").unwrap(),
+            },
+            price: Some(Decimal::from_str("10.125").unwrap()),
+            quantity: Decimal::from_str("100.0").unwrap(),
+            time_in_force: TimeInForce::GTC,
+            display_quantity: Some(Decimal::from_str("10.0").unwrap()),
+            min_quantity: Some(Decimal::from_str("20.0").unwrap()),
+            post_only: true,
+            reduce_only: true,
+            hidden: true,
+            idempotency_key: Some(Uuid::new_v4()),
+        };
+
+        let json = serde_json::to_string(&create_order_request).unwrap();
+        let deserialized: CreateOrderRequest = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(deserialized.item_id, create_order_request.item_id);
+        assert_eq!(deserialized.user_id, create_order_request.user_id);
+        assert!(matches!(deserialized.order_side, OrderSide::Sell));
+        assert!(matches!(
+            deserialized.order_type,
+            OrderType::StopLimit { trigger, limit }
+                if trigger == Decimal::from_str("9.5").unwrap()
+                    && limit == Decimal::from_str("9.25").unwrap()
+        ));
+        assert_eq!(deserialized.price.unwrap().to_string(), "10.125");
+        assert_eq!(deserialized.quantity.to_string(), "100.0");
+        assert!(matches!(deserialized.time_in_force, TimeInForce::GTC));
+        assert_eq!(
+            deserialized.display_quantity,
+            create_order_request.display_quantity
+        );
+        assert_eq!(deserialized.min_quantity, create_order_request.min_quantity);
+        assert!(deserialized.post_only);
+        assert!(deserialized.reduce_only);
+        assert!(deserialized.hidden);
+        assert_eq!(
+            deserialized.idempotency_key,
+            create_order_request.idempotency_key
+        );
+        assert_eq!(serde_json::to_string(&deserialized).unwrap(), json);
+    }
+
+    #[test]
+    fn should_serialize_enums_as_string_tags() {
+        assert_eq!(serde_json::to_string(&OrderSide::Buy).unwrap(), "\"Buy\"");
+        assert_eq!(serde_json::to_string(&TimeInForce::GTC).unwrap(), "\"GTC\"");
+        assert_eq!(
+            serde_json::to_string(&OrderStatus::PartiallyFilled).unwrap(),
+            "\"PartiallyFilled\""
+        );
+        assert_eq!(
+            serde_json::to_string(&OrderType::Market).unwrap(),
+            "\"Market\""
+        );
+    }
+
+    #[test]
+    fn should_round_trip_order_book_snapshot_through_json() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        for (order_side, price, quantity) in [
+            (OrderSide::Sell, "10.0", "50.0"),
+            (OrderSide::Buy, "10.0", "20.0"),
+            (OrderSide::Buy, "9.0", "15.0"),
+        ] {
+            order_book
+                .add_order(CreateOrderRequest {
+                    item_id,
+                    user_id: Uuid::new_v4(),
+                    order_side,
+                    order_type: OrderType::Limit,
+                    price: Some(Decimal::from_str(price).unwrap()),
+                    quantity: Decimal::from_str(quantity).unwrap(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let json = serde_json::to_string(&order_book.snapshot()).unwrap();
+        let snapshot: OrderBookSnapshot = serde_json::from_str(&json).unwrap();
+        let restored_order_book = OrderBookService::restore(snapshot);
+
+        assert_eq!(
+            restored_order_book.depth(item_id, 5).bids,
+            order_book.depth(item_id, 5).bids
+        );
+        assert_eq!(
+            restored_order_book.depth(item_id, 5).asks,
+            order_book.depth(item_id, 5).asks
+        );
+        assert_eq!(restored_order_book.trades.len(), 1);
+    }
+
+    #[test]
+    fn should_write_trade_log_as_json_lines() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        for (order_side, price, quantity) in [
+            (OrderSide::Sell, "10.0", "50.0"),
+            (OrderSide::Sell, "11.0", "50.0"),
+            (OrderSide::Buy, "11.0", "70.0"),
+        ] {
+            order_book
+                .add_order(CreateOrderRequest {
+                    item_id,
+                    user_id: Uuid::new_v4(),
+                    order_side,
+                    order_type: OrderType::Limit,
+                    price: Some(Decimal::from_str(price).unwrap()),
+                    quantity: Decimal::from_str(quantity).unwrap(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let mut log = Vec::new();
+        order_book.write_trade_log(&mut log).unwrap();
+
+        let trades: Vec<Trade> = String::from_utf8(log)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].id, order_book.trades[0].id);
+        assert_eq!(trades[1].quantity, Decimal::from_str("20.0").unwrap());
+    }
+
+    #[test]
+    fn should_replay_recorded_session_into_identical_book() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let mut commands = Vec::new();
+        for (order_side, price, quantity) in [
+            (OrderSide::Sell, "10.0", "50.0"),
+            (OrderSide::Sell, "11.0", "40.0"),
+            (OrderSide::Buy, "9.0", "30.0"),
+            (OrderSide::Buy, "10.0", "20.0"),
+        ] {
+            let request = CreateOrderRequest {
+                item_id,
+                user_id: Uuid::new_v4(),
+                order_side,
+                order_type: OrderType::Limit,
+                price: Some(Decimal::from_str(price).unwrap()),
+                quantity: Decimal::from_str(quantity).unwrap(),
+                ..Default::default()
+            };
+            let order = order_book.add_order(request.clone()).unwrap();
+            commands.push(BookCommand::Submit {
+                order_id: order.id,
+                request,
+            });
+        }
+        let cancelled_order_id = match &commands[1] {
+            BookCommand::Submit { order_id, .. } => *order_id,
+            _ => unreachable!(),
+        };
+        order_book.cancel_order(cancelled_order_id);
+        commands.push(BookCommand::Cancel {
+            order_id: cancelled_order_id,
+        });
+
+        let log: String = commands
+            .iter()
+            .map(|command| serde_json::to_string(command).unwrap() + "\n")
+            .collect();
+        let replayed_order_book =
+            OrderBookService::replay(OrderBookConfig::default(), log.as_bytes()).unwrap();
+
+        assert!(replayed_order_book.structurally_eq(&order_book));
+        assert!(matches!(
+            replayed_order_book
+                .get_order_by_id(cancelled_order_id)
+                .unwrap()
+                .status,
+            OrderStatus::Cancelled
+        ));
+    }
+
+    #[test]
+    fn should_report_line_of_malformed_replay_command() {
+        let log = format!(
+            "{}\n\nnot json\n",
+            serde_json::to_string(&BookCommand::Cancel {
+                order_id: Uuid::new_v4(),
+            })
+            .unwrap()
+        );
+
+        let result = OrderBookService::replay(OrderBookConfig::default(), log.as_bytes());
+
+        assert!(matches!(result, Err(ReplayError::Parse { line: 3, .. })));
+    }
+}
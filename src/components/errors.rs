@@ -0,0 +1,170 @@
+use std::fmt;
+
+use rust_decimal::Decimal;
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OrderError {
+    NegativePrice,
+    MissingPrice,
+    NonPositiveQuantity,
+    NonPositiveDisplayQuantity,
+    InvalidMinQuantity,
+    NegativeTriggerPrice,
+    AlreadyExpired,
+    MarketNoLiquidity,
+    MarketPriceTooFar {
+        market: Decimal,
+        order: Decimal,
+        max_deviation: Decimal,
+    },
+    QuantityBelowMinimum {
+        quantity: Decimal,
+        minimum: Decimal,
+    },
+    QuantityAboveMaximum {
+        quantity: Decimal,
+        maximum: Decimal,
+    },
+    PriceNotTickAligned {
+        price: Decimal,
+        tick_size: Decimal,
+    },
+    ExcessPrecision {
+        value: Decimal,
+        decimal_places: u32,
+    },
+    ItemHalted,
+    ItemNotInBook,
+    AuctionInProgress,
+    PostOnlyWouldCross,
+    ReduceOnlyWouldIncrease,
+    OpenOrderLimitReached {
+        limit: usize,
+    },
+    OrderNotFound,
+    OrderNotActive,
+    QuantityBelowFilled,
+    RepriceBreachesCircuitBreaker,
+    Internal,
+}
+
+impl fmt::Display for OrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OrderError::NegativePrice => write!(f, "Price cannot be negative"),
+            OrderError::MissingPrice => write!(f, "Limit orders require a price"),
+            OrderError::NonPositiveQuantity => write!(f, "Quantity must be greater than zero"),
+            OrderError::NonPositiveDisplayQuantity => {
+                write!(f, "Display quantity must be greater than zero")
+            }
+            OrderError::InvalidMinQuantity => write!(
+                f,
+                "Minimum fill quantity must be greater than zero and at most the order quantity"
+            ),
+            OrderError::NegativeTriggerPrice => write!(f, "Trigger price cannot be negative"),
+            OrderError::AlreadyExpired => write!(f, "Expiry date is not in the future"),
+            OrderError::MarketNoLiquidity => write!(
+                f,
+                "Market order cannot be placed without any existing orders to determine price"
+            ),
+            OrderError::MarketPriceTooFar {
+                market,
+                order,
+                max_deviation,
+            } => write!(
+                f,
+                "Market order price cannot be more than {}% away from the current market price. Current market price: {}, Order price: {}",
+                (max_deviation * Decimal::ONE_HUNDRED).normalize(),
+                market,
+                order
+            ),
+            OrderError::QuantityBelowMinimum { quantity, minimum } => write!(
+                f,
+                "Quantity {} is below the minimum order size {}",
+                quantity, minimum
+            ),
+            OrderError::QuantityAboveMaximum { quantity, maximum } => write!(
+                f,
+                "Quantity {} is above the maximum order size {}",
+                quantity, maximum
+            ),
+            OrderError::PriceNotTickAligned { price, tick_size } => write!(
+                f,
+                "Price {} is not a multiple of the tick size {}",
+                price, tick_size
+            ),
+            OrderError::ExcessPrecision {
+                value,
+                decimal_places,
+            } => write!(
+                f,
+                "Value {} has more than {} decimal places",
+                value, decimal_places
+            ),
+            OrderError::ItemHalted => write!(f, "Trading is halted for this item"),
+            OrderError::ItemNotInBook => write!(f, "This book does not trade the item"),
+            OrderError::AuctionInProgress => write!(
+                f,
+                "Only resting limit orders are accepted during an auction"
+            ),
+            OrderError::PostOnlyWouldCross => write!(f, "Post-only order would cross the spread"),
+            OrderError::ReduceOnlyWouldIncrease => {
+                write!(f, "Reduce-only order would increase the position")
+            }
+            OrderError::OpenOrderLimitReached { limit } => {
+                write!(f, "User already has the maximum of {} open orders", limit)
+            }
+            OrderError::OrderNotFound => write!(f, "Order not found"),
+            OrderError::OrderNotActive => write!(f, "Order is no longer open"),
+            OrderError::QuantityBelowFilled => {
+                write!(f, "Quantity cannot be less than quantity filled")
+            }
+            OrderError::RepriceBreachesCircuitBreaker => {
+                write!(f, "New price could trade outside the circuit breaker band")
+            }
+            OrderError::Internal => write!(f, "Order book is in an inconsistent state"),
+        }
+    }
+}
+
+impl std::error::Error for OrderError {}
+
+#[cfg(feature = "serde")]
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(std::io::Error),
+    Parse {
+        line: usize,
+        source: serde_json::Error,
+    },
+}
+
+#[cfg(feature = "serde")]
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplayError::Io(error) => write!(f, "Failed to read replay log: {}", error),
+            ReplayError::Parse { line, source } => {
+                write!(f, "Invalid command on line {}: {}", line, source)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::error::Error for ReplayError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ReplayError::Io(error) => Some(error),
+            ReplayError::Parse { source, .. } => Some(source),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<std::io::Error> for ReplayError {
+    fn from(error: std::io::Error) -> Self {
+        ReplayError::Io(error)
+    }
+}
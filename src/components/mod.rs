@@ -1,5 +1,17 @@
+mod book;
+pub mod clock;
+pub mod config;
 pub mod dto;
+pub mod errors;
 pub mod services;
+pub mod single_item;
+pub mod sync_service;
 
+#[cfg(test)]
+mod dto_test;
 #[cfg(test)]
 mod services_test;
+#[cfg(test)]
+mod single_item_test;
+#[cfg(test)]
+mod sync_service_test;
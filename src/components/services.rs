@@ -1,47 +1,610 @@
 use std::{
-    cmp::min,
-    collections::{BTreeMap, HashMap, VecDeque},
-    str::FromStr,
+    cmp::{max, min},
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque},
+    sync::Arc,
 };
 
+use crate::components::book::Books;
+use crate::components::clock::{Clock, SystemClock};
+use crate::components::config::{
+    MatchPricePolicy, OrderBookConfig, PrecisionConfig, SelfTradePrevention, SizeLimits,
+};
 use crate::components::dto::{
-    CreateOrderRequest, Order, OrderSide, OrderStatus, OrderType, TimeInForce, Trade,
+    AuctionResult, BookCommand, BookDepth, BookStats, Candle, CreateOrderRequest, Fill,
+    ImpactEstimate, MatchResult, Order, OrderBookSnapshot, OrderEvent, OrderOutcome, OrderSide,
+    OrderStatus, OrderType, PositionState, PreviewResult, TimeInForce, Trade, TradedTotals,
 };
-use chrono::{DateTime, Utc};
-use rust_decimal::Decimal;
+use crate::components::errors::OrderError;
+#[cfg(feature = "serde")]
+use crate::components::errors::ReplayError;
+use crate::components::single_item::SingleItemOrderBook;
+use chrono::{DateTime, DurationRound, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
 use uuid::Uuid;
 
+const BPS_DENOMINATOR: Decimal = Decimal::from_parts(10_000, 0, 0, false, 0);
+
+type EventListener = Box<dyn FnMut(&OrderEvent) + Send + Sync>;
+type TradeListener = Box<dyn FnMut(&Trade, &Order, &Order) + Send + Sync>;
+
+// Listeners cannot be cloned, so a cloned book starts without one.
+struct ListenerSlot<T>(Option<T>);
+
+impl<T> Default for ListenerSlot<T> {
+    fn default() -> Self {
+        ListenerSlot(None)
+    }
+}
+
+impl<T> Clone for ListenerSlot<T> {
+    fn clone(&self) -> Self {
+        ListenerSlot(None)
+    }
+}
+
+#[derive(Clone)]
 pub struct OrderBookService {
     orders: HashMap<Uuid, Order>,
-    buy_orders: HashMap<Uuid, BTreeMap<Decimal, VecDeque<Uuid>>>,
-    sell_orders: HashMap<Uuid, BTreeMap<Decimal, VecDeque<Uuid>>>,
+    books: Books,
+    halted: HashSet<Uuid>,
+    auctions: HashSet<Uuid>,
+    idempotency_keys: HashMap<Uuid, Uuid>,
     pub trades: Vec<Trade>,
+    evicted_trades: usize,
+    traded_totals: HashMap<Uuid, TradedTotals>,
+    positions: HashMap<Uuid, HashMap<Uuid, PositionState>>,
+    config: OrderBookConfig,
+    sequence: u64,
+    book_sequences: HashMap<Uuid, u64>,
+    commands: Vec<BookCommand>,
+    clock: Arc<dyn Clock>,
+    event_listener: ListenerSlot<EventListener>,
+    trade_listener: ListenerSlot<TradeListener>,
 }
 
 impl OrderBookService {
     pub fn new() -> Self {
+        Self::new_with_config(OrderBookConfig::default())
+    }
+
+    pub fn with_capacity(items: usize) -> Self {
+        let mut order_book = Self::new();
+        order_book.books.reserve(items);
+        order_book
+    }
+
+    pub fn new_with_config(config: OrderBookConfig) -> Self {
+        Self::with_books(config, Books::default())
+    }
+
+    pub(crate) fn with_books(config: OrderBookConfig, books: Books) -> Self {
         OrderBookService {
             orders: Default::default(),
-            buy_orders: Default::default(),
-            sell_orders: Default::default(),
+            books,
+            halted: Default::default(),
+            auctions: Default::default(),
+            idempotency_keys: Default::default(),
             trades: Default::default(),
+            evicted_trades: 0,
+            traded_totals: Default::default(),
+            positions: Default::default(),
+            config,
+            sequence: 0,
+            book_sequences: Default::default(),
+            commands: Vec::new(),
+            clock: Arc::new(SystemClock),
+            event_listener: ListenerSlot::default(),
+            trade_listener: ListenerSlot::default(),
+        }
+    }
+
+    /// Convenience constructor for `SingleItemOrderBook`, whose engine holds
+    /// a single item's levels directly instead of a per-item map.
+    pub fn new_for_item(item_id: Uuid) -> SingleItemOrderBook {
+        SingleItemOrderBook::new(item_id)
+    }
+
+    pub fn set_event_listener(&mut self, f: EventListener) {
+        self.event_listener = ListenerSlot(Some(f));
+    }
+
+    /// Registers a callback run for every trade with the resolved buy and sell
+    /// orders, as they stood when the trade matched.
+    pub fn on_trade(&mut self, f: impl FnMut(&Trade, &Order, &Order) + Send + Sync + 'static) {
+        self.trade_listener = ListenerSlot(Some(Box::new(f)));
+    }
+
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    fn now(&self) -> DateTime<Utc> {
+        self.clock.now()
+    }
+
+    pub fn set_tick_size(&mut self, item_id: Uuid, tick_size: Decimal) {
+        self.config.tick_sizes.insert(item_id, tick_size);
+    }
+
+    pub fn set_size_limits(
+        &mut self,
+        item_id: Uuid,
+        min_quantity: Option<Decimal>,
+        max_quantity: Option<Decimal>,
+    ) {
+        self.config.size_limits.insert(
+            item_id,
+            SizeLimits {
+                min_quantity,
+                max_quantity,
+            },
+        );
+    }
+
+    pub fn halt_item(&mut self, item_id: Uuid) {
+        self.halted.insert(item_id);
+    }
+
+    pub fn resume_item(&mut self, item_id: Uuid) {
+        self.halted.remove(&item_id);
+    }
+
+    pub fn is_halted(&self, item_id: Uuid) -> bool {
+        self.halted.contains(&item_id)
+    }
+
+    pub fn start_auction(&mut self, item_id: Uuid) {
+        self.auctions.insert(item_id);
+    }
+
+    pub fn is_in_auction(&self, item_id: Uuid) -> bool {
+        self.auctions.contains(&item_id)
+    }
+
+    fn next_sequence(&mut self) -> u64 {
+        self.sequence += 1;
+        self.sequence
+    }
+
+    fn emit(&mut self, event: OrderEvent) {
+        if let Some(event_listener) = self.event_listener.0.as_mut() {
+            event_listener(&event);
+        }
+    }
+
+    fn emit_trade(&mut self, trade: &Trade) {
+        self.emit(OrderEvent::Traded(trade.clone()));
+        if let Some(trade_listener) = self.trade_listener.0.as_mut()
+            && let Some(buy_order) = self.orders.get(&trade.buy_order_id)
+            && let Some(sell_order) = self.orders.get(&trade.sell_order_id)
+        {
+            trade_listener(trade, buy_order, sell_order);
+        }
+    }
+
+    /// Compares orders, book levels, pending stops and trades while ignoring
+    /// timestamps and generated trade ids, so two engines fed the same commands
+    /// compare equal.
+    pub fn structurally_eq(&self, other: &Self) -> bool {
+        let orders_eq = self.orders.len() == other.orders.len()
+            && self.orders.values().all(|order| {
+                other
+                    .get_order_by_id(order.id)
+                    .is_some_and(|other_order| Self::orders_structurally_eq(order, other_order))
+            });
+        let trades_eq = self.trades.len() == other.trades.len()
+            && self
+                .trades
+                .iter()
+                .zip(&other.trades)
+                .all(|(trade, other_trade)| Self::trades_structurally_eq(trade, other_trade));
+
+        orders_eq
+            && trades_eq
+            && self.books == other.books
+            && self.halted == other.halted
+            && self.auctions == other.auctions
+            && self.idempotency_keys == other.idempotency_keys
+            && self.book_sequences == other.book_sequences
+            && self.evicted_trades == other.evicted_trades
+            && self.traded_totals == other.traded_totals
+            && self.positions == other.positions
+    }
+
+    fn orders_structurally_eq(order: &Order, other: &Order) -> bool {
+        order.id == other.id
+            && order.item_id == other.item_id
+            && order.user_id == other.user_id
+            && order.order_side == other.order_side
+            && order.order_type == other.order_type
+            && order.time_in_force == other.time_in_force
+            && order.price == other.price
+            && order.quantity == other.quantity
+            && order.quantity_filled == other.quantity_filled
+            && order.fill_notional == other.fill_notional
+            && order.display_quantity == other.display_quantity
+            && order.visible_quantity == other.visible_quantity
+            && order.status == other.status
+            && order.seq == other.seq
+    }
+
+    fn trades_structurally_eq(trade: &Trade, other: &Trade) -> bool {
+        trade.buy_order_id == other.buy_order_id
+            && trade.sell_order_id == other.sell_order_id
+            && trade.item_id == other.item_id
+            && trade.quantity == other.quantity
+            && trade.price == other.price
+            && trade.maker_fee == other.maker_fee
+            && trade.taker_fee == other.taker_fee
+            && trade.seq == other.seq
+            && trade.aggressor_seq == other.aggressor_seq
+    }
+
+    pub fn snapshot(&self) -> OrderBookSnapshot {
+        let mut order_ids: Vec<Uuid> = Vec::new();
+
+        for (_, book) in self.books.iter() {
+            for order_queue in book.bids.values().chain(book.asks.values()) {
+                order_ids.extend(order_queue.iter().copied());
+            }
+            order_ids.extend(book.stops.iter().copied());
         }
+
+        let queued_order_ids: HashSet<Uuid> = order_ids.iter().copied().collect();
+        let mut remaining_orders: Vec<&Order> = self
+            .orders
+            .values()
+            .filter(|order| !queued_order_ids.contains(&order.id))
+            .collect();
+        remaining_orders.sort_by_key(|order| order.seq);
+        order_ids.extend(remaining_orders.iter().map(|order| order.id));
+
+        OrderBookSnapshot {
+            config: self.config.clone(),
+            orders: order_ids
+                .iter()
+                .filter_map(|order_id| self.get_order_by_id(*order_id))
+                .cloned()
+                .collect(),
+            trades: self.trades.clone(),
+            book_sequences: self.book_sequences.clone(),
+            evicted_trades: self.evicted_trades,
+            traded_totals: self.traded_totals.clone(),
+            positions: self.positions.clone(),
+        }
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn write_trade_log<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        for trade in &self.trades {
+            serde_json::to_writer(&mut *writer, trade)?;
+            writer.write_all(b"\n")?;
+        }
+
+        Ok(())
+    }
+
+    pub fn restore(snapshot: OrderBookSnapshot) -> Self {
+        let mut order_book = Self::new_with_config(snapshot.config);
+        order_book.sequence = snapshot
+            .orders
+            .iter()
+            .map(|order| order.seq)
+            .chain(snapshot.trades.iter().map(|trade| trade.seq))
+            .max()
+            .unwrap_or(0);
+        order_book.trades = snapshot.trades;
+        order_book.evicted_trades = snapshot.evicted_trades;
+        order_book.traded_totals = snapshot.traded_totals;
+        order_book.positions = snapshot.positions;
+
+        for order in snapshot.orders {
+            let order_id = order.id;
+            let item_id = order.item_id;
+            let is_working = matches!(
+                order.status,
+                OrderStatus::Open | OrderStatus::PartiallyFilled
+            ) && order.quantity_filled < order.quantity;
+            let is_stop = matches!(
+                order.order_type,
+                OrderType::Stop { .. } | OrderType::StopLimit { .. }
+            );
+            if let Some(key) = order.idempotency_key {
+                order_book.idempotency_keys.entry(key).or_insert(order_id);
+            }
+            order_book.orders.insert(order_id, order);
+
+            if is_working && is_stop {
+                order_book.books.entry(item_id).stops.push(order_id);
+            } else if is_working {
+                order_book.add_to_book(order_id);
+            }
+        }
+        order_book.book_sequences = snapshot.book_sequences;
+
+        order_book
+    }
+
+    /// Rebuilds a book from a JSON-lines log of `BookCommand`s, as
+    /// `replay_commands` does, so `config` must be the original book's. Halts,
+    /// auctions and clock-dependent expiry are not in the log and are not
+    /// reproduced.
+    #[cfg(feature = "serde")]
+    pub fn replay<R: std::io::Read>(
+        config: OrderBookConfig,
+        reader: R,
+    ) -> Result<Self, ReplayError> {
+        use std::io::BufRead;
+
+        let mut commands = Vec::new();
+        for (index, line) in std::io::BufReader::new(reader).lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let command: BookCommand =
+                serde_json::from_str(&line).map_err(|source| ReplayError::Parse {
+                    line: index + 1,
+                    source,
+                })?;
+            commands.push(command);
+        }
+
+        Ok(Self::replay_commands(config, commands))
+    }
+
+    /// Rebuilds a book by applying `commands` to a fresh engine built with
+    /// `config`. Submissions keep their recorded order ids so later cancels
+    /// and amends still find them; commands the original book rejected are
+    /// rejected again and skipped. Given the original book's config and
+    /// `commands()`, the result is structurally equal to it. Halts, auctions,
+    /// config changes made after construction and clock-dependent expiry are
+    /// not in the log, so books that used them are not reproduced.
+    pub fn replay_commands(
+        config: OrderBookConfig,
+        commands: impl IntoIterator<Item = BookCommand>,
+    ) -> Self {
+        let mut order_book = Self::new_with_config(config);
+        for command in commands {
+            match command {
+                BookCommand::Submit { order_id, request } => {
+                    if let Ok(mut order) = order_book.prepare_order(request.clone()) {
+                        order.id = order_id;
+                        let _ = order_book.place_submitted_order(order, request);
+                    }
+                }
+                BookCommand::Cancel { order_id } => {
+                    order_book.cancel_order(order_id);
+                }
+                BookCommand::Amend {
+                    order_id,
+                    price,
+                    quantity,
+                } => {
+                    if let Some(quantity) = quantity {
+                        let _ = order_book.update_order_quantity(order_id, quantity);
+                    }
+                    if let Some(price) = price {
+                        order_book.update_order_price(order_id, price);
+                    }
+                }
+            }
+        }
+
+        order_book
+    }
+
+    /// Every submission, cancel and amend accepted through the public API, in
+    /// the order it was applied. Empty unless `record_commands` is set in the
+    /// config. Halts, auctions, config changes and the clock are not recorded.
+    pub fn commands(&self) -> &[BookCommand] {
+        &self.commands
+    }
+
+    fn record_command(&mut self, command: BookCommand) {
+        if self.config.record_commands {
+            self.commands.push(command);
+        }
+    }
+
+    pub fn add_order(
+        &mut self,
+        create_order_request: CreateOrderRequest,
+    ) -> Result<Order, OrderError> {
+        let result = self.submit_order(create_order_request);
+
+        match &result {
+            Ok(order) => debug_assert!(
+                self.is_halted(order.item_id)
+                    || self.is_in_auction(order.item_id)
+                    || !self.is_crossed(order.item_id),
+                "matching left the book for item {} crossed",
+                order.item_id
+            ),
+            Err(reason) => self.emit(OrderEvent::Rejected { reason: *reason }),
+        }
+
+        self.debug_assert_invariants();
+        result
+    }
+
+    fn submit_order(
+        &mut self,
+        create_order_request: CreateOrderRequest,
+    ) -> Result<Order, OrderError> {
+        if let Some(order) = self.idempotent_order(&create_order_request) {
+            return Ok(order.clone());
+        }
+
+        let order = self.prepare_order(create_order_request.clone())?;
+        self.check_open_order_limit(std::slice::from_ref(&order))?;
+        self.place_submitted_order(order, create_order_request)
+    }
+
+    /// The order already submitted under the request's idempotency key, if any.
+    fn idempotent_order(&self, create_order_request: &CreateOrderRequest) -> Option<&Order> {
+        create_order_request
+            .idempotency_key
+            .and_then(|key| self.idempotency_keys.get(&key))
+            .and_then(|order_id| self.get_order_by_id(*order_id))
+    }
+
+    fn place_submitted_order(
+        &mut self,
+        order: Order,
+        create_order_request: CreateOrderRequest,
+    ) -> Result<Order, OrderError> {
+        self.record_command(BookCommand::Submit {
+            order_id: order.id,
+            request: create_order_request,
+        });
+        self.place_order(order)
+    }
+
+    fn check_open_order_limit(&self, new_orders: &[Order]) -> Result<(), OrderError> {
+        let Some(limit) = self.config.max_open_orders_per_user else {
+            return Ok(());
+        };
+
+        let mut new_orders_per_user: HashMap<Uuid, usize> = HashMap::new();
+        for order in new_orders {
+            *new_orders_per_user.entry(order.user_id).or_default() += 1;
+        }
+
+        for (user_id, new_order_count) in new_orders_per_user {
+            let open_order_count = self
+                .orders
+                .values()
+                .filter(|order| {
+                    order.user_id == user_id
+                        && matches!(
+                            order.status,
+                            OrderStatus::Open | OrderStatus::PartiallyFilled
+                        )
+                })
+                .count();
+            if open_order_count + new_order_count > limit {
+                return Err(OrderError::OpenOrderLimitReached { limit });
+            }
+        }
+
+        Ok(())
     }
 
-    pub fn add_order(&mut self, create_order_request: CreateOrderRequest) -> Result<Order, String> {
-        if create_order_request.price < Decimal::ZERO {
-            return Err("Price cannot be negative".to_string());
+    fn prepare_order(
+        &self,
+        mut create_order_request: CreateOrderRequest,
+    ) -> Result<Order, OrderError> {
+        if !self.books.holds(create_order_request.item_id) {
+            return Err(OrderError::ItemNotInBook);
+        }
+
+        if self.is_halted(create_order_request.item_id) {
+            return Err(OrderError::ItemHalted);
+        }
+
+        if self.is_in_auction(create_order_request.item_id)
+            && (matches!(create_order_request.order_type, OrderType::Market)
+                || matches!(
+                    create_order_request.time_in_force,
+                    TimeInForce::IOC | TimeInForce::FOK
+                ))
+        {
+            return Err(OrderError::AuctionInProgress);
+        }
+
+        if let Some(precision) = self.config.precision {
+            Self::apply_precision(&mut create_order_request, precision)?;
+        }
+
+        let allow_negative_prices = self.config.allow_negative_prices;
+        if let Some(price) = create_order_request.price
+            && price < Decimal::ZERO
+            && !allow_negative_prices
+        {
+            return Err(OrderError::NegativePrice);
+        }
+
+        if create_order_request.price.is_none()
+            && matches!(create_order_request.order_type, OrderType::Limit)
+        {
+            return Err(OrderError::MissingPrice);
         }
 
         if create_order_request.quantity <= Decimal::ZERO {
-            return Err("Quantity must be greater than zero".to_string());
+            return Err(OrderError::NonPositiveQuantity);
+        }
+
+        if let Some(display_quantity) = create_order_request.display_quantity
+            && display_quantity <= Decimal::ZERO
+        {
+            return Err(OrderError::NonPositiveDisplayQuantity);
+        }
+
+        match create_order_request.order_type {
+            OrderType::Stop { trigger } if trigger < Decimal::ZERO && !allow_negative_prices => {
+                return Err(OrderError::NegativeTriggerPrice);
+            }
+            OrderType::StopLimit { trigger, limit }
+                if (trigger < Decimal::ZERO || limit < Decimal::ZERO) && !allow_negative_prices =>
+            {
+                return Err(OrderError::NegativeTriggerPrice);
+            }
+            _ => {}
+        }
+
+        if create_order_request.reduce_only {
+            let position =
+                self.position(create_order_request.user_id, create_order_request.item_id);
+            let reducible_quantity = match create_order_request.order_side {
+                OrderSide::Buy => -position,
+                OrderSide::Sell => position,
+            };
+            if reducible_quantity <= Decimal::ZERO {
+                return Err(OrderError::ReduceOnlyWouldIncrease);
+            }
+            create_order_request.quantity = min(create_order_request.quantity, reducible_quantity);
+        }
+
+        if let Some(min_quantity) = create_order_request.min_quantity
+            && (min_quantity <= Decimal::ZERO || min_quantity > create_order_request.quantity)
+        {
+            return Err(OrderError::InvalidMinQuantity);
+        }
+
+        self.check_size_limits(create_order_request.item_id, create_order_request.quantity)?;
+
+        let tick_checked_prices = match create_order_request.order_type {
+            OrderType::Limit => [create_order_request.price, None],
+            OrderType::Market => [None, None],
+            OrderType::Stop { trigger } => [Some(trigger), None],
+            OrderType::StopLimit { trigger, limit } => [Some(trigger), Some(limit)],
+        };
+        if let Some(tick_size) = self.config.tick_sizes.get(&create_order_request.item_id)
+            && let Some(price) = tick_checked_prices
+                .into_iter()
+                .flatten()
+                .find(|price| !Self::is_tick_aligned(*price, *tick_size))
+        {
+            return Err(OrderError::PriceNotTickAligned {
+                price,
+                tick_size: *tick_size,
+            });
         }
 
+        let now = self.now();
         let expires_at = match create_order_request.time_in_force {
-            TimeInForce::DAY => Some(Utc::now() + chrono::Duration::days(1)),
-            TimeInForce::IOC => Some(Utc::now()),
-            _ => None,
+            TimeInForce::GTC | TimeInForce::FOK => None,
+            TimeInForce::DAY => Some(now + chrono::Duration::days(1)),
+            TimeInForce::IOC => Some(now),
+            TimeInForce::GTD(expires_at) => Some(expires_at),
         };
+        if matches!(create_order_request.time_in_force, TimeInForce::GTD(_))
+            && self.is_expired(expires_at)
+        {
+            return Err(OrderError::AlreadyExpired);
+        }
 
         let mut order = Order {
             id: Uuid::new_v4(),
@@ -49,336 +612,2217 @@ impl OrderBookService {
             user_id: create_order_request.user_id,
             order_side: create_order_request.order_side,
             order_type: create_order_request.order_type,
-            price: create_order_request.price,
+            price: create_order_request.price.unwrap_or_default(),
             quantity: create_order_request.quantity,
             quantity_filled: Decimal::ZERO,
+            fill_notional: Decimal::ZERO,
+            display_quantity: create_order_request.display_quantity,
+            visible_quantity: match create_order_request.display_quantity {
+                Some(display_quantity) => min(display_quantity, create_order_request.quantity),
+                None => create_order_request.quantity,
+            },
+            min_quantity: create_order_request.min_quantity,
+            hidden: create_order_request.hidden,
+            idempotency_key: create_order_request.idempotency_key,
+            price_protected: false,
             time_in_force: create_order_request.time_in_force,
             status: OrderStatus::Open,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
+            created_at: now,
+            updated_at: now,
             expires_at,
+            seq: 0,
         };
 
         if matches!(order.order_type, OrderType::Market) {
-            match self.get_current_market_price(order.item_id, order.order_side) {
-                Some(market_price) => {
-                    let price_difference = match order.order_side {
-                        OrderSide::Buy if market_price > order.price => market_price - order.price,
-                        OrderSide::Sell if market_price < order.price => order.price - market_price,
-                        _ => Decimal::ZERO,
-                    };
+            let market_price = self
+                .get_current_market_price(order.item_id, order.order_side)
+                .ok_or(OrderError::MarketNoLiquidity)?;
 
-                    if price_difference > (order.price * Decimal::from_str("0.05").unwrap()) {
-                        return Err(format!(
-                            "Market order price cannot be more than 5% away from the current market price. Current market price: {}, Order price: {}",
-                            market_price, order.price
-                        ));
+            if let Some(protection_price) = create_order_request.price {
+                let price_difference = match order.order_side {
+                    OrderSide::Buy if market_price > protection_price => {
+                        market_price - protection_price
+                    }
+                    OrderSide::Sell if market_price < protection_price => {
+                        protection_price - market_price
                     }
-                    order.price = market_price;
+                    _ => Decimal::ZERO,
+                };
+
+                let max_deviation = self.config.max_market_deviation;
+                if price_difference > protection_price.abs() * max_deviation {
+                    return Err(OrderError::MarketPriceTooFar {
+                        market: market_price,
+                        order: protection_price,
+                        max_deviation,
+                    });
+                }
+                order.price_protected = true;
+            }
+            order.price = market_price;
+        }
+
+        if create_order_request.post_only && self.crosses_book(&order) {
+            return Err(OrderError::PostOnlyWouldCross);
+        }
+
+        if let OrderType::StopLimit { limit, .. } = order.order_type {
+            order.price = limit;
+        }
+
+        Ok(order)
+    }
+
+    fn check_size_limits(&self, item_id: Uuid, quantity: Decimal) -> Result<(), OrderError> {
+        let Some(size_limits) = self.config.size_limits.get(&item_id) else {
+            return Ok(());
+        };
+
+        if let Some(minimum) = size_limits.min_quantity
+            && quantity < minimum
+        {
+            return Err(OrderError::QuantityBelowMinimum { quantity, minimum });
+        }
+        if let Some(maximum) = size_limits.max_quantity
+            && quantity > maximum
+        {
+            return Err(OrderError::QuantityAboveMaximum { quantity, maximum });
+        }
+
+        Ok(())
+    }
+
+    fn round_to_precision(
+        value: Decimal,
+        decimal_places: u32,
+        precision: PrecisionConfig,
+    ) -> Result<Decimal, OrderError> {
+        if value.round_dp(decimal_places) == value {
+            return Ok(value);
+        }
+        match precision.rounding.strategy() {
+            Some(strategy) => Ok(value.round_dp_with_strategy(decimal_places, strategy)),
+            None => Err(OrderError::ExcessPrecision {
+                value,
+                decimal_places,
+            }),
+        }
+    }
+
+    fn apply_precision(
+        create_order_request: &mut CreateOrderRequest,
+        precision: PrecisionConfig,
+    ) -> Result<(), OrderError> {
+        let round = |value: Decimal, decimal_places: u32| {
+            Self::round_to_precision(value, decimal_places, precision)
+        };
+
+        if let Some(price) = create_order_request.price {
+            create_order_request.price = Some(round(price, precision.price_dp)?);
+        }
+        create_order_request.order_type = match create_order_request.order_type {
+            OrderType::Stop { trigger } => OrderType::Stop {
+                trigger: round(trigger, precision.price_dp)?,
+            },
+            OrderType::StopLimit { trigger, limit } => OrderType::StopLimit {
+                trigger: round(trigger, precision.price_dp)?,
+                limit: round(limit, precision.price_dp)?,
+            },
+            order_type => order_type,
+        };
+        create_order_request.quantity = round(create_order_request.quantity, precision.size_dp)?;
+        if let Some(display_quantity) = create_order_request.display_quantity {
+            create_order_request.display_quantity =
+                Some(round(display_quantity, precision.size_dp)?);
+        }
+        if let Some(min_quantity) = create_order_request.min_quantity {
+            create_order_request.min_quantity = Some(round(min_quantity, precision.size_dp)?);
+        }
+
+        Ok(())
+    }
+
+    fn place_order(&mut self, mut order: Order) -> Result<Order, OrderError> {
+        order.seq = self.next_sequence();
+        if let Some(key) = order.idempotency_key {
+            self.idempotency_keys.entry(key).or_insert(order.id);
+        }
+        self.orders.insert(order.id, order.clone());
+        self.emit(OrderEvent::Accepted(order.clone()));
+
+        if matches!(
+            order.order_type,
+            OrderType::Stop { .. } | OrderType::StopLimit { .. }
+        ) {
+            self.books.entry(order.item_id).stops.push(order.id);
+            return Ok(order);
+        }
+
+        if self.is_in_auction(order.item_id) {
+            self.add_to_book(order.id);
+            return Ok(order);
+        }
+
+        if self.below_min_quantity(&order) {
+            self.cancel(order.id);
+        } else {
+            self.match_order(&mut order);
+        }
+
+        self.get_order_by_id(order.id)
+            .cloned()
+            .ok_or(OrderError::Internal)
+    }
+
+    /// Validates every request before placing any. A request whose
+    /// idempotency key was already submitted, earlier in the book or earlier
+    /// in the batch, is not placed again; its slot holds the keyed order.
+    pub fn add_orders_atomic(
+        &mut self,
+        create_order_requests: Vec<CreateOrderRequest>,
+    ) -> Result<Vec<Order>, OrderError> {
+        let mut batch_keys = HashSet::new();
+        let is_new: Vec<bool> = create_order_requests
+            .iter()
+            .map(|create_order_request| {
+                self.idempotent_order(create_order_request).is_none()
+                    && create_order_request
+                        .idempotency_key
+                        .is_none_or(|key| batch_keys.insert(key))
+            })
+            .collect();
+
+        let prepared_orders = create_order_requests
+            .iter()
+            .zip(&is_new)
+            .filter(|(_, is_new)| **is_new)
+            .map(|(create_order_request, _)| self.prepare_order(create_order_request.clone()))
+            .collect::<Result<Vec<Order>, OrderError>>()
+            .and_then(|orders| {
+                self.check_open_order_limit(&orders)?;
+                Ok(orders)
+            });
+
+        let mut prepared_orders = match prepared_orders {
+            Ok(orders) => orders.into_iter(),
+            Err(reason) => {
+                self.emit(OrderEvent::Rejected { reason });
+                return Err(reason);
+            }
+        };
+
+        create_order_requests
+            .into_iter()
+            .zip(is_new)
+            .map(|(create_order_request, is_new)| {
+                if is_new {
+                    let order = prepared_orders.next().ok_or(OrderError::Internal)?;
+                    self.place_submitted_order(order, create_order_request)
+                } else {
+                    self.idempotent_order(&create_order_request)
+                        .cloned()
+                        .ok_or(OrderError::Internal)
                 }
-                None => return Err(
-                    "Market order cannot be placed without any existing orders to determine price"
-                        .to_string(),
-                ),
+            })
+            .collect()
+    }
+
+    /// Cancels `order_id` and submits the request in its place. A request whose
+    /// idempotency key was already submitted returns the keyed order and leaves
+    /// `order_id` alone.
+    pub fn replace_order(
+        &mut self,
+        order_id: Uuid,
+        create_order_request: CreateOrderRequest,
+    ) -> Result<Order, OrderError> {
+        if let Some(order) = self.idempotent_order(&create_order_request) {
+            return Ok(order.clone());
+        }
+
+        let result = self.prepare_replacement(order_id, create_order_request.clone());
+
+        match result {
+            Ok(order) => {
+                self.withdraw_order(order_id);
+                self.emit(OrderEvent::Cancelled { order_id });
+                self.record_command(BookCommand::Cancel { order_id });
+                self.place_submitted_order(order, create_order_request)
+            }
+            Err(reason) => {
+                self.emit(OrderEvent::Rejected { reason });
+                Err(reason)
+            }
+        }
+    }
+
+    fn prepare_replacement(
+        &self,
+        order_id: Uuid,
+        create_order_request: CreateOrderRequest,
+    ) -> Result<Order, OrderError> {
+        let existing_order = self
+            .get_order_by_id(order_id)
+            .ok_or(OrderError::OrderNotFound)?;
+        if !matches!(
+            existing_order.status,
+            OrderStatus::Open | OrderStatus::PartiallyFilled
+        ) {
+            return Err(OrderError::OrderNotActive);
+        }
+
+        self.prepare_order(create_order_request)
+    }
+
+    pub fn add_order_with_result(
+        &mut self,
+        create_order_request: CreateOrderRequest,
+    ) -> (MatchResult, Option<Order>) {
+        match self.add_order(create_order_request) {
+            Ok(order) => (MatchResult::from(&order), Some(order)),
+            Err(reason) => (MatchResult::Rejected(reason), None),
+        }
+    }
+
+    pub fn add_order_with_trades(
+        &mut self,
+        create_order_request: CreateOrderRequest,
+    ) -> Result<OrderOutcome, OrderError> {
+        let trade_cursor = self.trade_cursor();
+        let order = self.add_order(create_order_request)?;
+
+        Ok(OrderOutcome {
+            order,
+            trades: self.trades_since(trade_cursor).to_vec(),
+        })
+    }
+
+    /// Simulates `add_order` on a scratch copy of the request's item, so the
+    /// preview follows the real matching rules exactly while `self` stays
+    /// untouched and no events are emitted.
+    pub fn preview(&self, create_order_request: &CreateOrderRequest) -> PreviewResult {
+        let mut scratch_book = self.scratch_for(create_order_request);
+        let outcome = match scratch_book.add_order_with_trades(create_order_request.clone()) {
+            Ok(outcome) => outcome,
+            Err(reason) => {
+                return PreviewResult {
+                    status: Err(reason),
+                    fills: Vec::new(),
+                    quantity_filled: Decimal::ZERO,
+                    average_price: None,
+                };
             }
+        };
+
+        let order_id = outcome.order.id;
+        let fills: Vec<(Decimal, Decimal)> = outcome
+            .trades
+            .iter()
+            .filter(|trade| trade.buy_order_id == order_id || trade.sell_order_id == order_id)
+            .map(|trade| (trade.price, trade.quantity))
+            .collect();
+        let notional: Decimal = fills.iter().map(|(price, quantity)| price * quantity).sum();
+
+        PreviewResult {
+            status: Ok(outcome.order.status),
+            average_price: notional.checked_div(outcome.order.quantity_filled),
+            quantity_filled: outcome.order.quantity_filled,
+            fills,
+        }
+    }
+
+    /// A copy of just the state `create_order_request` can read or touch: its
+    /// item's book and orders, traded totals and halt and auction flags, plus
+    /// the submitting user's open orders and position for the per-user checks.
+    /// Trades, the command log and every other item are left behind.
+    fn scratch_for(&self, create_order_request: &CreateOrderRequest) -> Self {
+        let item_id = create_order_request.item_id;
+        let user_id = create_order_request.user_id;
+        let keyed_order_id = create_order_request
+            .idempotency_key
+            .and_then(|key| self.idempotency_keys.get_key_value(&key));
+
+        let orders = self
+            .orders
+            .values()
+            .filter(|order| {
+                let is_active = matches!(
+                    order.status,
+                    OrderStatus::Open | OrderStatus::PartiallyFilled
+                );
+                (is_active && (order.item_id == item_id || order.user_id == user_id))
+                    || keyed_order_id.is_some_and(|(_, order_id)| *order_id == order.id)
+            })
+            .map(|order| (order.id, order.clone()))
+            .collect();
+
+        fn for_item<T: Copy>(values: &HashMap<Uuid, T>, item_id: Uuid) -> HashMap<Uuid, T> {
+            values
+                .get(&item_id)
+                .map(|value| (item_id, *value))
+                .into_iter()
+                .collect()
+        }
+
+        OrderBookService {
+            orders,
+            books: self.books.only(item_id),
+            halted: self.halted.clone(),
+            auctions: self.auctions.clone(),
+            idempotency_keys: keyed_order_id
+                .map(|(key, order_id)| (*key, *order_id))
+                .into_iter()
+                .collect(),
+            trades: Vec::new(),
+            evicted_trades: 0,
+            traded_totals: for_item(&self.traded_totals, item_id),
+            positions: self
+                .positions
+                .get(&user_id)
+                .map(|user_positions| (user_id, user_positions.clone()))
+                .into_iter()
+                .collect(),
+            config: self.config.clone(),
+            sequence: self.sequence,
+            book_sequences: for_item(&self.book_sequences, item_id),
+            commands: Vec::new(),
+            clock: Arc::clone(&self.clock),
+            event_listener: ListenerSlot::default(),
+            trade_listener: ListenerSlot::default(),
+        }
+    }
+
+    /// Walks the opposite side the way a market order of `quantity` would,
+    /// without changing the book. Price deviation limits, self-trade
+    /// prevention and fees are not applied.
+    pub fn estimate_impact(
+        &self,
+        item_id: Uuid,
+        side: OrderSide,
+        quantity: Decimal,
+    ) -> ImpactEstimate {
+        let opposite_side = match side {
+            OrderSide::Buy => OrderSide::Sell,
+            OrderSide::Sell => OrderSide::Buy,
+        };
+        let quantity = quantity.max(Decimal::ZERO);
+        let mut remaining_quantity = quantity;
+        let mut notional = Decimal::ZERO;
+        let mut worst_price = None;
+
+        for resting_order in self
+            .resting_orders(item_id, opposite_side)
+            .filter(|order| !self.is_expired(order.expires_at))
+        {
+            if remaining_quantity.is_zero() {
+                break;
+            }
+
+            let fill_quantity = min(
+                remaining_quantity,
+                resting_order.quantity - resting_order.quantity_filled,
+            );
+            notional += resting_order.price * fill_quantity;
+            remaining_quantity -= fill_quantity;
+            worst_price = Some(resting_order.price);
+        }
+
+        let quantity_filled = quantity - remaining_quantity;
+        ImpactEstimate {
+            average_price: notional.checked_div(quantity_filled),
+            worst_price,
+            quantity_filled,
+            unfilled_quantity: remaining_quantity,
+        }
+    }
+
+    pub fn get_orders(&self) -> &HashMap<Uuid, Order> {
+        &self.orders
+    }
+
+    pub fn summary(&self) -> String {
+        let open_orders = self
+            .orders
+            .values()
+            .filter(|order| {
+                matches!(
+                    order.status,
+                    OrderStatus::Open | OrderStatus::PartiallyFilled
+                )
+            })
+            .count();
+
+        let mut volume_by_item: BTreeMap<Uuid, (Decimal, usize)> = BTreeMap::new();
+        for trade in &self.trades {
+            let (volume, trade_count) = volume_by_item.entry(trade.item_id).or_default();
+            *volume += trade.quantity;
+            *trade_count += 1;
+        }
+
+        let mut lines = vec![format!(
+            "Open orders: {}, trades: {}",
+            open_orders,
+            self.trades.len()
+        )];
+        for (item_id, (volume, trade_count)) in volume_by_item {
+            lines.push(format!(
+                "Item {}: traded volume {} across {} trades",
+                item_id, volume, trade_count
+            ));
+        }
+
+        lines.join("\n")
+    }
+
+    /// Counter bumped on every change to the item's resting book: orders
+    /// joining or leaving a level, fills and in-place amends. Feed consumers
+    /// can compare it between updates to detect gaps. Zero for an unseen item.
+    pub fn book_sequence(&self, item_id: Uuid) -> u64 {
+        self.book_sequences.get(&item_id).copied().unwrap_or(0)
+    }
+
+    /// Number of trades ever recorded, including any evicted under
+    /// `max_trades`. Pass it back to `trades_since` on the next poll.
+    pub fn trade_cursor(&self) -> usize {
+        self.evicted_trades + self.trades.len()
+    }
+
+    /// Trades recorded after `cursor`, where `cursor` is the `trade_cursor()` a
+    /// consumer observed on its previous poll. Trades already evicted are
+    /// skipped, so a stale cursor yields every retained trade.
+    pub fn trades_since(&self, cursor: usize) -> &[Trade] {
+        let start = cursor.saturating_sub(self.evicted_trades);
+        self.trades.get(start..).unwrap_or_default()
+    }
+
+    pub fn trades_for_item(&self, item_id: Uuid) -> Vec<&Trade> {
+        self.trades
+            .iter()
+            .filter(|trade| trade.item_id == item_id)
+            .collect()
+    }
+
+    pub fn open_orders_for_user(&self, user_id: Uuid) -> Vec<&Order> {
+        let mut open_orders: Vec<&Order> = self
+            .orders
+            .values()
+            .filter(|order| {
+                order.user_id == user_id
+                    && matches!(
+                        order.status,
+                        OrderStatus::Open | OrderStatus::PartiallyFilled
+                    )
+            })
+            .collect();
+        open_orders.sort_by_key(|order| (order.created_at, order.seq));
+
+        open_orders
+    }
+
+    pub fn orders_by_status(&self, status: OrderStatus) -> Vec<&Order> {
+        let mut orders: Vec<&Order> = self
+            .orders
+            .values()
+            .filter(|order| order.status == status)
+            .collect();
+        orders.sort_by_key(|order| (order.created_at, order.seq));
+
+        orders
+    }
+
+    pub fn trades_for_user(&self, user_id: Uuid) -> Vec<&Trade> {
+        let is_user_order = |order_id: Uuid| {
+            self.get_order_by_id(order_id)
+                .is_some_and(|order| order.user_id == user_id)
+        };
+
+        self.trades
+            .iter()
+            .filter(|trade| is_user_order(trade.buy_order_id) || is_user_order(trade.sell_order_id))
+            .collect()
+    }
+
+    /// Volume-weighted average trade price for `item_id`, optionally limited to
+    /// trades at or after `since`. The quotient keeps `Decimal`'s full 28-digit
+    /// precision; callers needing a fixed scale should round the result.
+    pub fn vwap(&self, item_id: Uuid, since: Option<DateTime<Utc>>) -> Option<Decimal> {
+        let (notional, volume) = self
+            .trades
+            .iter()
+            .filter(|trade| trade.item_id == item_id)
+            .filter(|trade| since.is_none_or(|since| trade.timestamp >= since))
+            .fold(
+                (Decimal::ZERO, Decimal::ZERO),
+                |(notional, volume), trade| {
+                    (
+                        notional + trade.price * trade.quantity,
+                        volume + trade.quantity,
+                    )
+                },
+            );
+
+        notional.checked_div(volume)
+    }
+
+    pub fn fills_of(&self, order_id: Uuid) -> Vec<Fill> {
+        self.trades
+            .iter()
+            .filter(|trade| trade.buy_order_id == order_id || trade.sell_order_id == order_id)
+            .map(|trade| Fill {
+                trade_id: trade.id,
+                quantity: trade.quantity,
+                price: trade.price,
+                timestamp: trade.timestamp,
+            })
+            .collect()
+    }
+
+    pub fn total_volume(&self, item_id: Uuid) -> Decimal {
+        self.traded_totals
+            .get(&item_id)
+            .map_or(Decimal::ZERO, |totals| totals.volume)
+    }
+
+    pub fn total_notional(&self, item_id: Uuid) -> Decimal {
+        self.traded_totals
+            .get(&item_id)
+            .map_or(Decimal::ZERO, |totals| totals.notional)
+    }
+
+    pub fn average_fill_price(&self, order_id: Uuid) -> Option<Decimal> {
+        let order = self.get_order_by_id(order_id)?;
+        order.fill_notional.checked_div(order.quantity_filled)
+    }
+
+    /// Executed share of the order's requested quantity, so an IOC or market
+    /// order whose remainder was cancelled reports less than one.
+    pub fn fill_ratio(&self, order_id: Uuid) -> Option<Decimal> {
+        let order = self.get_order_by_id(order_id)?;
+        let fill_ratio = order.quantity_filled.checked_div(order.quantity)?;
+        Some(fill_ratio.clamp(Decimal::ZERO, Decimal::ONE))
+    }
+
+    pub fn position(&self, user_id: Uuid, item_id: Uuid) -> Decimal {
+        self.position_state(user_id, item_id).quantity
+    }
+
+    /// Realized P&L of the user's fills in the item, before fees, using
+    /// weighted-average cost: fills that grow the position re-average its cost,
+    /// fills that shrink it realize the difference to that average. A fill that
+    /// flips the position opens the new side at the fill price.
+    pub fn realized_pnl(&self, user_id: Uuid, item_id: Uuid) -> Decimal {
+        self.position_state(user_id, item_id).realized_pnl
+    }
+
+    fn position_state(&self, user_id: Uuid, item_id: Uuid) -> PositionState {
+        self.positions
+            .get(&user_id)
+            .and_then(|positions| positions.get(&item_id))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    fn apply_fill(position: &mut PositionState, fill: Decimal, price: Decimal) {
+        if position.quantity.is_zero()
+            || position.quantity.is_sign_positive() == fill.is_sign_positive()
+        {
+            position.average_cost = (position.quantity.abs() * position.average_cost
+                + fill.abs() * price)
+                / (position.quantity.abs() + fill.abs());
+        } else {
+            let closed_quantity = min(position.quantity.abs(), fill.abs());
+            let pnl_per_unit = if position.quantity.is_sign_positive() {
+                price - position.average_cost
+            } else {
+                position.average_cost - price
+            };
+            position.realized_pnl += closed_quantity * pnl_per_unit;
+
+            if fill.abs() > position.quantity.abs() {
+                position.average_cost = price;
+            }
+        }
+        position.quantity += fill;
+    }
+
+    pub fn last_price(&self, item_id: Uuid) -> Option<Decimal> {
+        self.traded_totals
+            .get(&item_id)
+            .and_then(|totals| totals.last_price)
+    }
+
+    pub fn high_24h(&self, item_id: Uuid, now: DateTime<Utc>) -> Option<Decimal> {
+        self.trades_in_24h(item_id, now)
+            .map(|trade| trade.price)
+            .max()
+    }
+
+    pub fn low_24h(&self, item_id: Uuid, now: DateTime<Utc>) -> Option<Decimal> {
+        self.trades_in_24h(item_id, now)
+            .map(|trade| trade.price)
+            .min()
+    }
+
+    pub fn volume_24h(&self, item_id: Uuid, now: DateTime<Utc>) -> Decimal {
+        self.trades_in_24h(item_id, now)
+            .map(|trade| trade.quantity)
+            .sum()
+    }
+
+    /// Buckets the item's trades into epoch-aligned intervals. Intervals with
+    /// no trades are skipped; a non-positive interval yields no candles.
+    pub fn candles(&self, item_id: Uuid, interval: chrono::Duration) -> Vec<Candle> {
+        let mut candles: BTreeMap<DateTime<Utc>, Candle> = BTreeMap::new();
+
+        for trade in self.trades.iter().filter(|trade| trade.item_id == item_id) {
+            let Ok(start) = trade.timestamp.duration_trunc(interval) else {
+                continue;
+            };
+            candles
+                .entry(start)
+                .and_modify(|candle| {
+                    candle.high = candle.high.max(trade.price);
+                    candle.low = candle.low.min(trade.price);
+                    candle.close = trade.price;
+                    candle.volume += trade.quantity;
+                })
+                .or_insert(Candle {
+                    start,
+                    open: trade.price,
+                    high: trade.price,
+                    low: trade.price,
+                    close: trade.price,
+                    volume: trade.quantity,
+                });
+        }
+
+        candles.into_values().collect()
+    }
+
+    fn trades_in_24h(&self, item_id: Uuid, now: DateTime<Utc>) -> impl Iterator<Item = &Trade> {
+        let window_start = now - chrono::Duration::hours(24);
+        self.trades.iter().filter(move |trade| {
+            trade.item_id == item_id && trade.timestamp > window_start && trade.timestamp <= now
+        })
+    }
+
+    /// Whether repricing the active order `order_id` to `new_price` would make
+    /// it trade against the opposite side. Unknown, inactive and untriggered
+    /// stop orders never cross.
+    pub fn would_cross(&self, order_id: Uuid, new_price: Decimal) -> bool {
+        let Some(order) = self.get_order_by_id(order_id).filter(|order| {
+            matches!(
+                order.status,
+                OrderStatus::Open | OrderStatus::PartiallyFilled
+            )
+        }) else {
+            return false;
+        };
+
+        self.crosses_book(&Order {
+            price: new_price,
+            ..order.clone()
+        })
+    }
+
+    fn crosses_book(&self, order: &Order) -> bool {
+        match order.order_type {
+            OrderType::Stop { .. } | OrderType::StopLimit { .. } => false,
+            _ => match self.get_current_market_price(order.item_id, order.order_side) {
+                Some(best_price) => self.can_match_price(order, best_price),
+                None => false,
+            },
+        }
+    }
+
+    fn is_tick_aligned(price: Decimal, tick_size: Decimal) -> bool {
+        tick_size <= Decimal::ZERO || (price % tick_size).is_zero()
+    }
+
+    /// An order expires at its `expires_at` instant, the same cutoff
+    /// `expire_orders` sweeps with.
+    fn is_expired(&self, expires_at: Option<DateTime<Utc>>) -> bool {
+        match expires_at {
+            Some(expiry) => expiry <= self.now(),
+            None => false,
+        }
+    }
+
+    pub fn get_current_market_price(
+        &self,
+        item_id: Uuid,
+        order_side: OrderSide,
+    ) -> Option<Decimal> {
+        match order_side {
+            OrderSide::Buy => self.top_price(item_id, OrderSide::Sell),
+            OrderSide::Sell => self.top_price(item_id, OrderSide::Buy),
+        }
+    }
+
+    /// Best price on one side counting hidden orders, which matching still
+    /// trades against even though the public quotes leave them out.
+    fn top_price(&self, item_id: Uuid, side: OrderSide) -> Option<Decimal> {
+        let levels = self.books.get(item_id)?.side(side);
+        match side {
+            OrderSide::Buy => levels.keys().next_back().copied(),
+            OrderSide::Sell => levels.keys().next().copied(),
+        }
+    }
+
+    pub fn best_bid(&self, item_id: Uuid) -> Option<Decimal> {
+        self.best_level(item_id, OrderSide::Buy)
+            .map(|(price, _)| price)
+    }
+
+    pub fn best_ask(&self, item_id: Uuid) -> Option<Decimal> {
+        self.best_level(item_id, OrderSide::Sell)
+            .map(|(price, _)| price)
+    }
+
+    /// Best price and displayed quantity of the first level holding a visible
+    /// order, counted as `depth` counts it: hidden orders are left out and
+    /// icebergs contribute only their current slice.
+    pub fn best_level(&self, item_id: Uuid, side: OrderSide) -> Option<(Decimal, Decimal)> {
+        let price_map = self.books.get(item_id)?.side(side);
+        let levels: Box<dyn Iterator<Item = (&Decimal, &VecDeque<Uuid>)>> = match side {
+            OrderSide::Buy => Box::new(price_map.iter().rev()),
+            OrderSide::Sell => Box::new(price_map.iter()),
+        };
+
+        levels
+            .map(|(price, order_queue)| (*price, self.level_quantity(order_queue)))
+            .find(|(_, visible_quantity)| *visible_quantity > Decimal::ZERO)
+    }
+
+    pub fn spread(&self, item_id: Uuid) -> Option<Decimal> {
+        Some(self.best_ask(item_id)? - self.best_bid(item_id)?)
+    }
+
+    pub fn mid_price(&self, item_id: Uuid) -> Option<Decimal> {
+        Some((self.best_bid(item_id)? + self.best_ask(item_id)?) / Decimal::TWO)
+    }
+
+    /// Spread as basis points of the absolute mid price, rounded to four
+    /// decimal places with banker's rounding.
+    pub fn spread_bps(&self, item_id: Uuid) -> Option<Decimal> {
+        let mid_price = self.mid_price(item_id)?;
+        if mid_price.is_zero() {
+            return None;
+        }
+
+        let spread_bps = self.spread(item_id)? / mid_price.abs() * BPS_DENOMINATOR;
+        Some(spread_bps.round_dp(4))
+    }
+
+    /// Mid price weighted toward the thinner side: each best price is weighted
+    /// by the quantity resting at the opposite best level.
+    pub fn micro_price(&self, item_id: Uuid) -> Option<Decimal> {
+        let (bid_price, bid_quantity) = self.best_level(item_id, OrderSide::Buy)?;
+        let (ask_price, ask_quantity) = self.best_level(item_id, OrderSide::Sell)?;
+        let total_quantity = bid_quantity + ask_quantity;
+        if total_quantity <= Decimal::ZERO {
+            return None;
+        }
+
+        Some((bid_price * ask_quantity + ask_price * bid_quantity) / total_quantity)
+    }
+
+    /// A bid at or above the ask would already have matched, so outside halts
+    /// and auctions a crossed book means matching went wrong.
+    pub fn is_crossed(&self, item_id: Uuid) -> bool {
+        match (
+            self.top_price(item_id, OrderSide::Buy),
+            self.top_price(item_id, OrderSide::Sell),
+        ) {
+            (Some(best_bid), Some(best_ask)) => best_bid >= best_ask,
+            _ => false,
+        }
+    }
+
+    pub fn depth(&self, item_id: Uuid, levels: usize) -> BookDepth {
+        let book = self.books.get(item_id);
+        let bids = match book {
+            Some(book) => book
+                .bids
+                .iter()
+                .rev()
+                .map(|(price, order_queue)| (*price, self.level_quantity(order_queue)))
+                .filter(|(_, quantity)| *quantity > Decimal::ZERO)
+                .take(levels)
+                .collect(),
+            None => Vec::new(),
+        };
+
+        let asks = match book {
+            Some(book) => book
+                .asks
+                .iter()
+                .map(|(price, order_queue)| (*price, self.level_quantity(order_queue)))
+                .filter(|(_, quantity)| *quantity > Decimal::ZERO)
+                .take(levels)
+                .collect(),
+            None => Vec::new(),
+        };
+
+        BookDepth { bids, asks }
+    }
+
+    pub fn stats(&self, item_id: Uuid) -> BookStats {
+        let side_totals = |side: OrderSide| match self.books.get(item_id) {
+            Some(book) => book
+                .side(side)
+                .values()
+                .map(|order_queue| self.level_quantity(order_queue))
+                .filter(|quantity| *quantity > Decimal::ZERO)
+                .fold((0, Decimal::ZERO), |(levels, total), quantity| {
+                    (levels + 1, total + quantity)
+                }),
+            None => (0, Decimal::ZERO),
+        };
+        let (bid_levels, total_bid_qty) = side_totals(OrderSide::Buy);
+        let (ask_levels, total_ask_qty) = side_totals(OrderSide::Sell);
+
+        let traded_totals = self
+            .traded_totals
+            .get(&item_id)
+            .copied()
+            .unwrap_or_default();
+
+        BookStats {
+            open_orders: self
+                .orders
+                .values()
+                .filter(|order| order.item_id == item_id)
+                .filter(|order| {
+                    matches!(
+                        order.status,
+                        OrderStatus::Open | OrderStatus::PartiallyFilled
+                    )
+                })
+                .count(),
+            bid_levels,
+            ask_levels,
+            total_bid_qty,
+            total_ask_qty,
+            last_trade_price: traded_totals.last_price,
+            trade_count: traded_totals.trade_count,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.books.iter().all(|(_, book)| !book.has_levels())
+    }
+
+    pub fn resting_count(&self, item_id: Uuid) -> usize {
+        self.books
+            .get(item_id)
+            .into_iter()
+            .flat_map(|book| book.bids.values().chain(book.asks.values()))
+            .map(|order_queue| order_queue.len())
+            .sum()
+    }
+
+    pub fn items(&self) -> impl Iterator<Item = Uuid> {
+        self.books
+            .iter()
+            .filter(|(_, book)| book.has_levels())
+            .map(|(item_id, _)| item_id)
+            .collect::<BTreeSet<Uuid>>()
+            .into_iter()
+    }
+
+    pub fn all_stats(&self) -> HashMap<Uuid, BookStats> {
+        self.items()
+            .map(|item_id| (item_id, self.stats(item_id)))
+            .collect()
+    }
+
+    pub fn book_checksum(&self, item_id: Uuid, levels: usize) -> u32 {
+        let depth = self.depth(item_id, levels);
+        let mut fields: Vec<String> = Vec::new();
+
+        for level in 0..max(depth.bids.len(), depth.asks.len()) {
+            for (price, quantity) in [depth.bids.get(level), depth.asks.get(level)]
+                .into_iter()
+                .flatten()
+            {
+                fields.push(format!("{}:{}", price.normalize(), quantity.normalize()));
+            }
+        }
+
+        crc32fast::hash(fields.join(":").as_bytes())
+    }
+
+    pub fn resting_orders(&self, item_id: Uuid, side: OrderSide) -> impl Iterator<Item = &Order> {
+        let levels: Box<dyn Iterator<Item = &VecDeque<Uuid>>> =
+            match (self.books.get(item_id), side) {
+                (Some(book), OrderSide::Buy) => Box::new(book.bids.values().rev()),
+                (Some(book), OrderSide::Sell) => Box::new(book.asks.values()),
+                (None, _) => Box::new(std::iter::empty()),
+            };
+
+        levels
+            .flatten()
+            .filter_map(|order_id| self.get_order_by_id(*order_id))
+    }
+
+    /// Orders accepted but not yet resting in the continuous book, such as
+    /// untriggered stops, in submission order.
+    pub fn pending_orders(&self, item_id: Uuid) -> Vec<&Order> {
+        self.books
+            .get(item_id)
+            .into_iter()
+            .flat_map(|book| &book.stops)
+            .filter_map(|order_id| self.get_order_by_id(*order_id))
+            .collect()
+    }
+
+    pub fn level2(&self, item_id: Uuid, side: OrderSide, depth: usize) -> Vec<Order> {
+        self.resting_orders(item_id, side)
+            .filter(|order| !order.hidden)
+            .take(depth)
+            .cloned()
+            .collect()
+    }
+
+    fn level_quantity(&self, order_queue: &VecDeque<Uuid>) -> Decimal {
+        order_queue
+            .iter()
+            .filter_map(|order_id| self.get_order_by_id(*order_id))
+            .filter(|order| !order.hidden)
+            .map(|order| order.visible_quantity)
+            .sum()
+    }
+
+    pub fn get_order_by_id(&self, order_id: Uuid) -> Option<&Order> {
+        self.orders.get(&order_id)
+    }
+
+    pub fn get_mutable_order_by_id(&mut self, order_id: Uuid) -> Option<&mut Order> {
+        self.orders.get_mut(&order_id)
+    }
+
+    /// Uncrosses the item's book at the single price that maximizes matched volume
+    /// and ends any auction started with `start_auction`. Ties are broken by the
+    /// smallest imbalance between bid and ask volume at that price, then by the
+    /// lowest price. Every crossing order fills at the clearing price, walking each
+    /// side in priority order.
+    pub fn run_auction(&mut self, item_id: Uuid) -> Option<AuctionResult> {
+        self.auctions.remove(&item_id);
+
+        let auction_orders = |side: OrderSide| -> Vec<(Uuid, Decimal, Decimal)> {
+            self.resting_orders(item_id, side)
+                .filter(|order| !self.is_expired(order.expires_at))
+                .map(|order| {
+                    (
+                        order.id,
+                        order.price,
+                        order.quantity - order.quantity_filled,
+                    )
+                })
+                .filter(|(_, _, remaining_quantity)| *remaining_quantity > Decimal::ZERO)
+                .collect()
+        };
+        let bids = auction_orders(OrderSide::Buy);
+        let asks = auction_orders(OrderSide::Sell);
+
+        let mut candidate_prices: Vec<Decimal> = bids
+            .iter()
+            .chain(asks.iter())
+            .map(|(_, price, _)| *price)
+            .collect();
+        candidate_prices.sort();
+        candidate_prices.dedup();
+
+        let mut best: Option<(Decimal, Decimal, Decimal)> = None;
+        for price in candidate_prices {
+            let demand: Decimal = bids
+                .iter()
+                .filter(|(_, bid_price, _)| *bid_price >= price)
+                .map(|(_, _, remaining_quantity)| *remaining_quantity)
+                .sum();
+            let supply: Decimal = asks
+                .iter()
+                .filter(|(_, ask_price, _)| *ask_price <= price)
+                .map(|(_, _, remaining_quantity)| *remaining_quantity)
+                .sum();
+            let volume = min(demand, supply);
+            let imbalance = (demand - supply).abs();
+
+            let is_better = match best {
+                None => true,
+                Some((_, best_volume, best_imbalance)) => {
+                    volume > best_volume || (volume == best_volume && imbalance < best_imbalance)
+                }
+            };
+            if is_better {
+                best = Some((price, volume, imbalance));
+            }
+        }
+
+        let (clearing_price, matched_volume, _) = best?;
+        if matched_volume <= Decimal::ZERO {
+            return None;
+        }
+
+        let mut remaining_volume = matched_volume;
+        let mut bid_iter = bids.into_iter();
+        let mut ask_iter = asks.into_iter();
+        let mut bid = bid_iter.next();
+        let mut ask = ask_iter.next();
+        let mut trades: Vec<Trade> = Vec::new();
+
+        while remaining_volume > Decimal::ZERO
+            && let (Some((bid_id, _, bid_remaining)), Some((ask_id, _, ask_remaining))) =
+                (bid.as_mut(), ask.as_mut())
+        {
+            let trade_quantity = min(remaining_volume, min(*bid_remaining, *ask_remaining));
+            let (bid_id, ask_id) = (*bid_id, *ask_id);
+            *bid_remaining -= trade_quantity;
+            *ask_remaining -= trade_quantity;
+            let bid_exhausted = bid_remaining.is_zero();
+            let ask_exhausted = ask_remaining.is_zero();
+            remaining_volume -= trade_quantity;
+
+            let aggressor_seq = [bid_id, ask_id]
+                .iter()
+                .filter_map(|order_id| self.get_order_by_id(*order_id))
+                .map(|order| order.seq)
+                .max()
+                .unwrap_or_default();
+            let trade = self.new_trade(
+                bid_id,
+                ask_id,
+                item_id,
+                clearing_price,
+                trade_quantity,
+                aggressor_seq,
+            );
+            self.emit_trade(&trade);
+            trades.push(trade);
+            self.fill_order(bid_id, trade_quantity);
+            self.fill_order(ask_id, trade_quantity);
+
+            if bid_exhausted {
+                bid = bid_iter.next();
+            }
+            if ask_exhausted {
+                ask = ask_iter.next();
+            }
+        }
+
+        self.record_trades(trades);
+        self.trigger_stop_orders(item_id, clearing_price, clearing_price);
+
+        Some(AuctionResult {
+            clearing_price,
+            matched_volume,
+        })
+    }
+
+    pub fn update_order_status(
+        &mut self,
+        order_id: Uuid,
+        new_status: OrderStatus,
+    ) -> Option<&Order> {
+        let now = self.now();
+        if let Some(order) = self.get_mutable_order_by_id(order_id) {
+            order.status = new_status;
+            order.updated_at = now;
+        } else {
+            return None;
+        }
+
+        self.emit(OrderEvent::StatusChanged {
+            order_id,
+            status: new_status,
+        });
+        self.get_order_by_id(order_id)
+    }
+
+    pub fn cancel_order(&mut self, order_id: Uuid) -> bool {
+        let cancelled = self.cancel(order_id);
+        if cancelled {
+            self.record_command(BookCommand::Cancel { order_id });
+        }
+
+        cancelled
+    }
+
+    /// Cancels like `cancel_order`, returning the order's final state instead
+    /// of a flag.
+    pub fn cancel_order_get(&mut self, order_id: Uuid) -> Option<Order> {
+        if !self.cancel_order(order_id) {
+            return None;
+        }
+
+        self.get_order_by_id(order_id).cloned()
+    }
+
+    fn cancel(&mut self, order_id: Uuid) -> bool {
+        let cancelled = self.withdraw_order(order_id);
+        if cancelled {
+            self.emit(OrderEvent::Cancelled { order_id });
+        }
+
+        self.debug_assert_invariants();
+        cancelled
+    }
+
+    pub fn cancel_all_for_user(&mut self, user_id: Uuid) -> Vec<Uuid> {
+        self.withdraw_active_orders(
+            |order| order.user_id == user_id,
+            |order_id| OrderEvent::Cancelled { order_id },
+        )
+    }
+
+    pub fn cancel_all_for_item(&mut self, item_id: Uuid) -> Vec<Uuid> {
+        self.withdraw_active_orders(
+            |order| order.item_id == item_id,
+            |order_id| OrderEvent::Cancelled { order_id },
+        )
+    }
+
+    pub fn cancel_level(&mut self, item_id: Uuid, side: OrderSide, price: Decimal) -> Vec<Uuid> {
+        let level_order_ids: Vec<Uuid> = self
+            .books
+            .get(item_id)
+            .and_then(|book| book.side(side).get(&price))
+            .map(|order_queue| order_queue.iter().copied().collect())
+            .unwrap_or_default();
+
+        level_order_ids
+            .into_iter()
+            .filter(|order_id| self.cancel_order(*order_id))
+            .collect()
+    }
+
+    fn withdraw_active_orders(
+        &mut self,
+        predicate: impl Fn(&Order) -> bool,
+        event: fn(Uuid) -> OrderEvent,
+    ) -> Vec<Uuid> {
+        let mut withdrawn_orders: Vec<&Order> = self
+            .orders
+            .values()
+            .filter(|order| {
+                matches!(
+                    order.status,
+                    OrderStatus::Open | OrderStatus::PartiallyFilled
+                )
+            })
+            .filter(|order| predicate(order))
+            .collect();
+        withdrawn_orders.sort_by_key(|order| order.seq);
+
+        let withdrawn_order_ids: Vec<Uuid> =
+            withdrawn_orders.iter().map(|order| order.id).collect();
+
+        for order_id in &withdrawn_order_ids {
+            self.withdraw_order(*order_id);
+            self.emit(event(*order_id));
+            self.record_command(BookCommand::Cancel {
+                order_id: *order_id,
+            });
+        }
+
+        withdrawn_order_ids
+    }
+
+    /// Checks that every order resting in the book is known, still active and
+    /// not yet fully filled, and that no empty level or item map is left
+    /// behind. Compiled out of release builds.
+    #[cfg(debug_assertions)]
+    pub(crate) fn debug_assert_invariants(&self) {
+        for (item_id, book) in self.books.iter() {
+            assert!(
+                matches!(self.books, Books::Single { .. }) || !book.is_empty(),
+                "item {item_id} has an empty book"
+            );
+            for (price, order_queue) in book.bids.iter().chain(&book.asks) {
+                assert!(
+                    !order_queue.is_empty(),
+                    "item {item_id} has an empty level at {price}"
+                );
+            }
+            for order_id in book.bids.values().chain(book.asks.values()).flatten() {
+                let order = self
+                    .orders
+                    .get(order_id)
+                    .unwrap_or_else(|| panic!("resting order {order_id} is missing from orders"));
+                assert!(
+                    !matches!(order.status, OrderStatus::Closed | OrderStatus::Cancelled),
+                    "resting order {order_id} has status {:?}",
+                    order.status
+                );
+                assert!(
+                    order.quantity_filled < order.quantity,
+                    "resting order {order_id} is fully filled"
+                );
+            }
+        }
+    }
+
+    #[cfg(not(debug_assertions))]
+    pub(crate) fn debug_assert_invariants(&self) {}
+
+    /// Forgets closed and cancelled orders that no retained trade refers to,
+    /// along with the idempotency keys pointing at them, returning how many
+    /// orders and keys were removed. Positions and traded totals are running
+    /// counters, so they are unaffected.
+    pub fn compact(&mut self) -> (usize, usize) {
+        let traded_order_ids: HashSet<Uuid> = self
+            .trades
+            .iter()
+            .flat_map(|trade| [trade.buy_order_id, trade.sell_order_id])
+            .collect();
+
+        let order_count = self.orders.len();
+        self.orders.retain(|order_id, order| {
+            matches!(
+                order.status,
+                OrderStatus::Open | OrderStatus::PartiallyFilled
+            ) || traded_order_ids.contains(order_id)
+        });
+
+        let key_count = self.idempotency_keys.len();
+        self.idempotency_keys
+            .retain(|_, order_id| self.orders.contains_key(order_id));
+
+        (
+            order_count - self.orders.len(),
+            key_count - self.idempotency_keys.len(),
+        )
+    }
+
+    fn withdraw_order(&mut self, order_id: Uuid) -> bool {
+        let now = self.now();
+        if let Some(order) = self.get_mutable_order_by_id(order_id)
+            && matches!(
+                order.status,
+                OrderStatus::Open | OrderStatus::PartiallyFilled
+            )
+        {
+            order.status = OrderStatus::Cancelled;
+            order.updated_at = now;
+            self.remove_from_book(order_id);
+            self.remove_from_stop_orders(order_id);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn expire_orders(&mut self, now: DateTime<Utc>) -> Vec<Uuid> {
+        self.withdraw_active_orders(
+            |order| matches!(order.expires_at, Some(expiry) if expiry <= now),
+            |order_id| OrderEvent::Expired { order_id },
+        )
+    }
+
+    /// `expire_orders` restricted to the orders of `item_id`.
+    pub fn expire_orders_for_item(&mut self, item_id: Uuid, now: DateTime<Utc>) -> Vec<Uuid> {
+        self.withdraw_active_orders(
+            |order| {
+                order.item_id == item_id
+                    && matches!(order.expires_at, Some(expiry) if expiry <= now)
+            },
+            |order_id| OrderEvent::Expired { order_id },
+        )
+    }
+
+    /// Withdraws every working order of `user_id`, including partially filled
+    /// ones and untriggered stops, reporting each as `Expired`. Intended for
+    /// cancel-on-disconnect when a user's session ends.
+    pub fn expire_user_orders(&mut self, user_id: Uuid) -> Vec<Uuid> {
+        self.withdraw_active_orders(
+            |order| order.user_id == user_id,
+            |order_id| OrderEvent::Expired { order_id },
+        )
+    }
+
+    pub fn expiring_within(&self, window: chrono::Duration, now: DateTime<Utc>) -> Vec<&Order> {
+        let horizon = now + window;
+        let mut expiring_orders: Vec<&Order> = self
+            .orders
+            .values()
+            .filter(|order| {
+                matches!(
+                    order.status,
+                    OrderStatus::Open | OrderStatus::PartiallyFilled
+                )
+            })
+            .filter(
+                |order| matches!(order.expires_at, Some(expiry) if expiry >= now && expiry <= horizon),
+            )
+            .collect();
+        expiring_orders.sort_by_key(|order| (order.expires_at, order.seq));
+
+        expiring_orders
+    }
+
+    pub fn update_order_quantity(
+        &mut self,
+        order_id: Uuid,
+        new_quantity: Decimal,
+    ) -> Result<&Order, OrderError> {
+        self.set_order_quantity(order_id, new_quantity)?;
+        self.record_command(BookCommand::Amend {
+            order_id,
+            price: None,
+            quantity: Some(new_quantity),
+        });
+
+        self.get_order_by_id(order_id)
+            .ok_or(OrderError::OrderNotFound)
+    }
+
+    fn set_order_quantity(
+        &mut self,
+        order_id: Uuid,
+        new_quantity: Decimal,
+    ) -> Result<&Order, OrderError> {
+        let now = self.now();
+        if new_quantity <= Decimal::ZERO {
+            return Err(OrderError::NonPositiveQuantity);
+        }
+
+        let order = self
+            .get_mutable_order_by_id(order_id)
+            .ok_or(OrderError::OrderNotFound)?;
+        if !matches!(
+            order.status,
+            OrderStatus::Open | OrderStatus::PartiallyFilled
+        ) {
+            return Err(OrderError::OrderNotActive);
+        }
+        if new_quantity < order.quantity_filled {
+            return Err(OrderError::QuantityBelowFilled);
+        }
+
+        let item_id = order.item_id;
+        let is_increase = new_quantity > order.quantity;
+        let is_exhausted = new_quantity == order.quantity_filled;
+        order.quantity = new_quantity;
+        let remaining_quantity = new_quantity - order.quantity_filled;
+        order.visible_quantity = match order.display_quantity {
+            Some(_) => min(order.visible_quantity, remaining_quantity),
+            None => remaining_quantity,
+        };
+        order.updated_at = now;
+        let closes_order = is_exhausted && !matches!(order.status, OrderStatus::Closed);
+        if closes_order {
+            order.status = OrderStatus::Closed;
+            self.remove_from_book(order_id);
+            self.emit(OrderEvent::StatusChanged {
+                order_id,
+                status: OrderStatus::Closed,
+            });
+        } else if is_increase && self.remove_from_book(order_id) {
+            self.add_to_book(order_id);
+        } else if self.is_resting(order_id) {
+            self.bump_book_sequence(item_id);
         }
 
-        self.orders.insert(order.id, order.clone());
-        self.execute_order_matching(&mut order);
+        self.get_order_by_id(order_id)
+            .ok_or(OrderError::OrderNotFound)
+    }
 
-        let updated_order = self.get_order_by_id(order.id).unwrap().clone();
+    /// Shrinks an active order's remaining quantity in place, so it keeps its
+    /// queue position. Reducing by the whole remainder or more cancels it.
+    pub fn reduce_order(&mut self, order_id: Uuid, delta: Decimal) -> Result<Order, OrderError> {
+        if delta <= Decimal::ZERO {
+            return Err(OrderError::NonPositiveQuantity);
+        }
 
-        if matches!(
-            updated_order.status,
+        let order = self
+            .get_order_by_id(order_id)
+            .ok_or(OrderError::OrderNotFound)?;
+        if !matches!(
+            order.status,
             OrderStatus::Open | OrderStatus::PartiallyFilled
         ) {
-            match updated_order.order_side {
-                OrderSide::Buy => {
-                    self.buy_orders
-                        .entry(updated_order.item_id)
-                        .or_default()
-                        .entry(updated_order.price)
-                        .or_default()
-                        .push_back(updated_order.id);
-                }
-                OrderSide::Sell => {
-                    self.sell_orders
-                        .entry(updated_order.item_id)
-                        .or_default()
-                        .entry(updated_order.price)
-                        .or_default()
-                        .push_back(updated_order.id);
-                }
-            }
+            return Err(OrderError::OrderNotActive);
         }
 
-        Ok(updated_order)
-    }
+        let new_quantity = order.quantity - delta;
+        if new_quantity <= order.quantity_filled {
+            return self
+                .cancel_order_get(order_id)
+                .ok_or(OrderError::OrderNotFound);
+        }
 
-    pub fn get_orders(&self) -> &HashMap<Uuid, Order> {
-        &self.orders
+        self.update_order_quantity(order_id, new_quantity).cloned()
     }
 
-    fn is_expired(&self, expires_at: Option<DateTime<Utc>>) -> bool {
-        match expires_at {
-            Some(expiry) => {
-                let now = Utc::now();
-                expiry < now
-            }
-            None => false,
+    /// Changes price and/or quantity in one call. A new price or a larger
+    /// quantity sends the order to the back of its level (and a new price
+    /// re-runs matching); a smaller quantity keeps its place.
+    pub fn amend_order(
+        &mut self,
+        order_id: Uuid,
+        new_price: Option<Decimal>,
+        new_quantity: Option<Decimal>,
+    ) -> Result<Order, OrderError> {
+        let (new_price, new_quantity) =
+            self.validate_amendment(order_id, new_price, new_quantity)?;
+
+        if let Some(new_quantity) = new_quantity {
+            self.update_order_quantity(order_id, new_quantity)?;
+        }
+
+        if let Some(price) = new_price {
+            self.update_order_price(order_id, price);
         }
+
+        self.get_order_by_id(order_id)
+            .cloned()
+            .ok_or(OrderError::OrderNotFound)
     }
 
-    pub fn get_current_market_price(
+    /// Checks an amend of an active order the way `add_order` checks a new
+    /// one: precision, sign, tick and size limits. Returns the price and
+    /// quantity to apply, rounded under `precision`; a price equal to the
+    /// current one comes back as `None`.
+    fn validate_amendment(
         &self,
-        item_id: Uuid,
-        order_side: OrderSide,
-    ) -> Option<Decimal> {
-        let price_map = match order_side {
-            OrderSide::Buy => self.sell_orders.get(&item_id)?,
-            OrderSide::Sell => self.buy_orders.get(&item_id)?,
+        order_id: Uuid,
+        new_price: Option<Decimal>,
+        new_quantity: Option<Decimal>,
+    ) -> Result<(Option<Decimal>, Option<Decimal>), OrderError> {
+        let order = self
+            .get_order_by_id(order_id)
+            .ok_or(OrderError::OrderNotFound)?;
+        if !matches!(
+            order.status,
+            OrderStatus::Open | OrderStatus::PartiallyFilled
+        ) {
+            return Err(OrderError::OrderNotActive);
+        }
+        let precision = self.config.precision;
+
+        let new_quantity = match new_quantity {
+            Some(mut quantity) => {
+                if let Some(precision) = precision {
+                    quantity = Self::round_to_precision(quantity, precision.size_dp, precision)?;
+                }
+                if quantity <= Decimal::ZERO {
+                    return Err(OrderError::NonPositiveQuantity);
+                }
+                if quantity < order.quantity_filled {
+                    return Err(OrderError::QuantityBelowFilled);
+                }
+                self.check_size_limits(order.item_id, quantity)?;
+                Some(quantity)
+            }
+            None => None,
         };
 
-        match order_side {
-            OrderSide::Buy => price_map.iter().next().map(|(price, _)| *price),
+        let new_price = match new_price {
+            Some(mut price) => {
+                if let Some(precision) = precision {
+                    price = Self::round_to_precision(price, precision.price_dp, precision)?;
+                }
+                if price < Decimal::ZERO && !self.config.allow_negative_prices {
+                    return Err(OrderError::NegativePrice);
+                }
+                if let Some(tick_size) = self.config.tick_sizes.get(&order.item_id)
+                    && !Self::is_tick_aligned(price, *tick_size)
+                {
+                    return Err(OrderError::PriceNotTickAligned {
+                        price,
+                        tick_size: *tick_size,
+                    });
+                }
+                if price != order.price
+                    && self.is_resting(order_id)
+                    && self.reprice_breaches_circuit_breaker(order, price)
+                {
+                    return Err(OrderError::RepriceBreachesCircuitBreaker);
+                }
+                Some(price).filter(|price| *price != order.price)
+            }
+            None => None,
+        };
 
-            OrderSide::Sell => price_map.iter().next_back().map(|(price, _)| *price),
-        }
+        Ok((new_price, new_quantity))
     }
 
-    pub fn get_order_by_id(&self, order_id: Uuid) -> Option<&Order> {
-        self.orders.get(&order_id)
-    }
+    /// Whether a resting order moved to `new_price` could fill at a price the
+    /// circuit breaker rejects. Every opposite level the new price crosses is
+    /// checked, whatever the order's size, so a reprice never trips the
+    /// breaker and loses the order to it.
+    fn reprice_breaches_circuit_breaker(&self, order: &Order, new_price: Decimal) -> bool {
+        let (Some(max_move), Some(reference_price)) =
+            (self.config.circuit_breaker, self.last_price(order.item_id))
+        else {
+            return false;
+        };
+        if self.is_halted(order.item_id) || self.is_in_auction(order.item_id) {
+            return false;
+        }
+        let Some(book) = self.books.get(order.item_id) else {
+            return false;
+        };
 
-    pub fn get_mutable_order_by_id(&mut self, order_id: Uuid) -> Option<&mut Order> {
-        self.orders.get_mut(&order_id)
+        let repriced_order = Order {
+            price: new_price,
+            ..order.clone()
+        };
+        let opposite_levels = match order.order_side {
+            OrderSide::Buy => &book.asks,
+            OrderSide::Sell => &book.bids,
+        };
+        opposite_levels
+            .keys()
+            .filter(|price| self.can_match_price(&repriced_order, **price))
+            .map(|price| self.execution_price(&repriced_order, *price))
+            .any(|execution_price| Self::breaches_band(execution_price, reference_price, max_move))
     }
 
-    pub fn update_order_status(
-        &mut self,
-        order_id: Uuid,
-        new_status: OrderStatus,
-    ) -> Option<&Order> {
-        if let Some(order) = self.get_mutable_order_by_id(order_id) {
-            order.status = new_status;
-            order.updated_at = Utc::now();
-            Some(order)
-        } else {
-            None
-        }
+    fn breaches_band(price: Decimal, reference_price: Decimal, max_move: Decimal) -> bool {
+        (price - reference_price).abs() > reference_price.abs() * max_move
     }
 
-    pub fn cancel_order(&mut self, order_id: Uuid) -> bool {
+    /// Moves an order to `new_price`, re-running matching if it was resting.
+    /// Returns `None`, leaving the order untouched, when the order is unknown
+    /// or inactive or the price fails the checks `amend_order` applies.
+    pub fn update_order_price(&mut self, order_id: Uuid, new_price: Decimal) -> Option<&Order> {
+        let (new_price, _) = self
+            .validate_amendment(order_id, Some(new_price), None)
+            .ok()?;
+        let Some(new_price) = new_price else {
+            return self.get_order_by_id(order_id);
+        };
+
+        let now = self.now();
+        let was_resting = self.remove_from_book(order_id);
+
         if let Some(order) = self.get_mutable_order_by_id(order_id) {
-            order.status = OrderStatus::Cancelled;
-            order.updated_at = Utc::now();
-            self.remove_from_book(order_id);
-            true
+            order.price = new_price;
+            // An untriggered stop-limit works at its limit once it fires.
+            if let OrderType::StopLimit { limit, .. } = &mut order.order_type {
+                *limit = new_price;
+            }
+            order.updated_at = now;
         } else {
-            false
+            return None;
         }
-    }
+        self.record_command(BookCommand::Amend {
+            order_id,
+            price: Some(new_price),
+            quantity: None,
+        });
 
-    pub fn update_order_quantity(
-        &mut self,
-        order_id: Uuid,
-        new_quantity: Decimal,
-    ) -> Option<&Order> {
-        if let Some(order) = self.orders.get_mut(&order_id) {
-            order.quantity = new_quantity;
-            order.updated_at = Utc::now();
-            Some(order)
-        } else {
-            None
+        if was_resting {
+            let mut order = self.get_order_by_id(order_id)?.clone();
+            if self.is_halted(order.item_id) || self.is_in_auction(order.item_id) {
+                self.add_to_book(order_id);
+            } else {
+                self.match_order(&mut order);
+            }
         }
+
+        self.get_order_by_id(order_id)
     }
 
-    pub fn update_order_price(&mut self, order_id: Uuid, new_price: Decimal) -> Option<&Order> {
-        if let Some(order) = self.get_mutable_order_by_id(order_id) {
-            order.price = new_price;
-            order.updated_at = Utc::now();
-            Some(order)
-        } else {
-            None
+    fn add_to_book(&mut self, order_id: Uuid) {
+        let order = match self.get_order_by_id(order_id) {
+            Some(order) => order,
+            None => return,
+        };
+
+        let item_id = order.item_id;
+        let price = order.price;
+        let hidden = order.hidden;
+        let order_side = order.order_side;
+        self.bump_book_sequence(item_id);
+
+        let order_queue = self
+            .books
+            .entry(item_id)
+            .side_mut(order_side)
+            .entry(price)
+            .or_default();
+        if hidden {
+            order_queue.push_back(order_id);
+            return;
         }
+
+        // Hidden orders yield to visible ones at the same price, so they are
+        // kept together at the back of the queue.
+        let hidden_tail = order_queue
+            .iter()
+            .rev()
+            .take_while(|queued_id| {
+                self.orders
+                    .get(*queued_id)
+                    .is_some_and(|queued_order| queued_order.hidden)
+            })
+            .count();
+        order_queue.insert(order_queue.len() - hidden_tail, order_id);
     }
 
-    fn remove_from_book(&mut self, order_id: Uuid) {
+    fn remove_from_book(&mut self, order_id: Uuid) -> bool {
         let order = match self.get_order_by_id(order_id) {
             Some(order) => order.clone(),
-            None => return,
+            None => return false,
         };
 
         let item_id = order.item_id;
         let price = order.price;
         let side = order.order_side;
 
-        let book = match side {
-            OrderSide::Buy => &mut self.buy_orders,
-            OrderSide::Sell => &mut self.sell_orders,
+        let mut removed = false;
+
+        if let Some(price_map) = self.books.get_mut(item_id).map(|book| book.side_mut(side))
+            && let Some(order_queue) = price_map.get_mut(&price)
+        {
+            let queue_length = order_queue.len();
+            order_queue.retain(|order_id_from_queue| *order_id_from_queue != order_id);
+            removed = order_queue.len() != queue_length;
+
+            if order_queue.is_empty() {
+                price_map.remove(&price);
+            }
+        }
+        self.books.prune(item_id);
+
+        if removed {
+            self.bump_book_sequence(item_id);
+        }
+
+        removed
+    }
+
+    fn is_resting(&self, order_id: Uuid) -> bool {
+        let Some(order) = self.get_order_by_id(order_id) else {
+            return false;
+        };
+        self.books
+            .get(order.item_id)
+            .and_then(|book| book.side(order.order_side).get(&order.price))
+            .is_some_and(|order_queue| order_queue.contains(&order_id))
+    }
+
+    fn bump_book_sequence(&mut self, item_id: Uuid) {
+        *self.book_sequences.entry(item_id).or_default() += 1;
+    }
+
+    fn remove_from_stop_orders(&mut self, order_id: Uuid) {
+        let item_id = match self.get_order_by_id(order_id) {
+            Some(order) => order.item_id,
+            None => return,
+        };
+
+        if let Some(book) = self.books.get_mut(item_id) {
+            book.stops
+                .retain(|stop_order_id| *stop_order_id != order_id);
+            self.books.prune(item_id);
+        }
+    }
+
+    fn trigger_stop_orders(
+        &mut self,
+        item_id: Uuid,
+        lowest_price: Decimal,
+        highest_price: Decimal,
+    ) {
+        let stop_queue = match self.books.get(item_id) {
+            Some(book) => book.stops.clone(),
+            None => return,
         };
 
-        if let Some(price_map) = book.get_mut(&item_id) {
-            if let Some(order_queue) = price_map.get_mut(&price) {
-                order_queue.retain(|order_id_from_queue| *order_id_from_queue != order_id);
+        let triggered: Vec<Uuid> = stop_queue
+            .into_iter()
+            .filter(|order_id| match self.get_order_by_id(*order_id) {
+                Some(order) => {
+                    let trigger = match order.order_type {
+                        OrderType::Stop { trigger } | OrderType::StopLimit { trigger, .. } => {
+                            trigger
+                        }
+                        _ => return false,
+                    };
 
-                if order_queue.is_empty() {
-                    price_map.remove(&price);
+                    match order.order_side {
+                        OrderSide::Buy => highest_price >= trigger,
+                        OrderSide::Sell => lowest_price <= trigger,
+                    }
                 }
+                None => false,
+            })
+            .collect();
+
+        for order_id in triggered {
+            self.remove_from_stop_orders(order_id);
+            self.activate_stop_order(order_id);
+        }
+    }
+
+    fn activate_stop_order(&mut self, order_id: Uuid) {
+        let now = self.now();
+        let order = match self.get_mutable_order_by_id(order_id) {
+            Some(order) if matches!(order.status, OrderStatus::Open) => order,
+            _ => return,
+        };
+
+        match order.order_type {
+            OrderType::Stop { .. } => order.order_type = OrderType::Market,
+            OrderType::StopLimit { limit, .. } => {
+                order.order_type = OrderType::Limit;
+                order.price = limit;
             }
+            _ => return,
+        }
+        order.updated_at = now;
 
-            if price_map.is_empty() {
-                book.remove(&item_id);
+        let (item_id, order_side) = (order.item_id, order.order_side);
+
+        if matches!(order.order_type, OrderType::Market) {
+            match self.get_current_market_price(item_id, order_side) {
+                Some(market_price) => {
+                    if let Some(order) = self.get_mutable_order_by_id(order_id) {
+                        order.price = market_price;
+                    }
+                }
+                None => {
+                    self.cancel(order_id);
+                    return;
+                }
             }
         }
+
+        let mut activated_order = match self.get_order_by_id(order_id) {
+            Some(order) => order.clone(),
+            None => return,
+        };
+        if self.below_min_quantity(&activated_order) {
+            self.cancel(order_id);
+        } else {
+            self.match_order(&mut activated_order);
+        }
     }
 
     fn fill_order(&mut self, order_id: Uuid, quantity_filled: Decimal) -> Option<&mut Order> {
-        let is_fully_filled = if let Some(order) = self.get_mutable_order_by_id(order_id) {
-            order.quantity_filled += quantity_filled;
+        let now = self.now();
+        let (is_fully_filled, is_replenished, previous_status) =
+            if let Some(order) = self.get_mutable_order_by_id(order_id) {
+                let previous_status = order.status;
+                order.quantity_filled += quantity_filled;
+                order.updated_at = now;
+                order.visible_quantity -= min(order.visible_quantity, quantity_filled);
 
-            if order.quantity_filled >= order.quantity {
-                order.status = OrderStatus::Closed;
-                true
+                if order.quantity_filled >= order.quantity {
+                    order.status = OrderStatus::Closed;
+                    (true, false, previous_status)
+                } else {
+                    order.status = OrderStatus::PartiallyFilled;
+                    match order.display_quantity {
+                        Some(display_quantity) if order.visible_quantity <= Decimal::ZERO => {
+                            order.visible_quantity =
+                                min(display_quantity, order.quantity - order.quantity_filled);
+                            (false, true, previous_status)
+                        }
+                        _ => (false, false, previous_status),
+                    }
+                }
             } else {
-                order.status = OrderStatus::PartiallyFilled;
-                false
-            }
-        } else {
-            return None;
-        };
+                return None;
+            };
 
         if is_fully_filled {
             self.remove_from_book(order_id);
+        } else if is_replenished && self.remove_from_book(order_id) {
+            self.add_to_book(order_id);
+        }
+
+        let status = if is_fully_filled {
+            OrderStatus::Closed
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+        if std::mem::discriminant(&previous_status) != std::mem::discriminant(&status) {
+            self.emit(OrderEvent::StatusChanged { order_id, status });
         }
 
         self.get_mutable_order_by_id(order_id)
     }
 
-    fn can_match_price(&self, incoming: &Order, resting: &Order) -> bool {
-        match (incoming.order_type, incoming.order_side) {
-            (OrderType::Market, _) => true,
-            (OrderType::Limit, OrderSide::Buy) => incoming.price >= resting.price,
-            (OrderType::Limit, OrderSide::Sell) => incoming.price <= resting.price,
+    fn record_trades(&mut self, trades: Vec<Trade>) {
+        for trade in &trades {
+            let notional = trade.price * trade.quantity;
+            let totals = self.traded_totals.entry(trade.item_id).or_default();
+            totals.volume += trade.quantity;
+            totals.notional += notional;
+            totals.trade_count += 1;
+            totals.last_price = Some(trade.price);
+
+            let mut fill_user = |order_id: Uuid| {
+                self.orders.get_mut(&order_id).map(|order| {
+                    order.fill_notional += notional;
+                    order.user_id
+                })
+            };
+            let buyer_id = fill_user(trade.buy_order_id);
+            let seller_id = fill_user(trade.sell_order_id);
+            if buyer_id != seller_id {
+                for (user_id, fill) in [(buyer_id, trade.quantity), (seller_id, -trade.quantity)] {
+                    if let Some(user_id) = user_id {
+                        let position = self
+                            .positions
+                            .entry(user_id)
+                            .or_default()
+                            .entry(trade.item_id)
+                            .or_default();
+                        Self::apply_fill(position, fill, trade.price);
+                    }
+                }
+            }
+            self.bump_book_sequence(trade.item_id);
+        }
+        self.trades.extend(trades);
+
+        if let Some(max_trades) = self.config.max_trades
+            && self.trades.len() > max_trades
+        {
+            let evicted = self.trades.len() - max_trades;
+            self.trades.drain(..evicted);
+            self.evicted_trades += evicted;
         }
     }
 
-    pub fn execute_order_matching(&mut self, incoming_order: &mut Order) {
-        let order_book_side = match incoming_order.order_side {
-            OrderSide::Buy => &self.sell_orders,
-            OrderSide::Sell => &self.buy_orders,
+    fn new_trade(
+        &mut self,
+        buy_order_id: Uuid,
+        sell_order_id: Uuid,
+        item_id: Uuid,
+        price: Decimal,
+        quantity: Decimal,
+        aggressor_seq: u64,
+    ) -> Trade {
+        let notional = price * quantity;
+        Trade {
+            id: Uuid::new_v4(),
+            buy_order_id,
+            sell_order_id,
+            item_id,
+            quantity,
+            price,
+            maker_fee: notional * self.config.fee_schedule.maker_bps / BPS_DENOMINATOR,
+            taker_fee: notional * self.config.fee_schedule.taker_bps / BPS_DENOMINATOR,
+            timestamp: self.now(),
+            seq: self.next_sequence(),
+            aggressor_seq,
+        }
+    }
+
+    fn execution_price(&self, incoming: &Order, resting_price: Decimal) -> Decimal {
+        if !matches!(
+            incoming.order_type,
+            OrderType::Limit | OrderType::StopLimit { .. }
+        ) {
+            return resting_price;
+        }
+
+        match self.config.match_price_policy {
+            MatchPricePolicy::MakerPrice => resting_price,
+            MatchPricePolicy::Midpoint => self.midpoint_price(incoming, resting_price),
+            MatchPricePolicy::AggressorPrice => incoming.price,
+        }
+    }
+
+    /// Midpoint of the two limits, rounded toward the resting price onto the
+    /// item's tick and, when precision is configured, to `price_dp`. Both
+    /// limits are valid prices, so the result stays between them.
+    fn midpoint_price(&self, incoming: &Order, resting_price: Decimal) -> Decimal {
+        let midpoint = (resting_price + incoming.price) / Decimal::TWO;
+        let toward_resting = if resting_price <= midpoint {
+            RoundingStrategy::ToNegativeInfinity
+        } else {
+            RoundingStrategy::ToPositiveInfinity
         };
 
-        let price_maps = match order_book_side.get(&incoming_order.item_id) {
-            Some(item) => item,
-            _ => {
-                return;
+        let mut price = midpoint;
+        if let Some(&tick_size) = self.config.tick_sizes.get(&incoming.item_id)
+            && tick_size > Decimal::ZERO
+        {
+            price = (price / tick_size).round_dp_with_strategy(0, toward_resting) * tick_size;
+        }
+        if let Some(precision) = self.config.precision {
+            price = price.round_dp_with_strategy(precision.price_dp, toward_resting);
+        }
+        price
+    }
+
+    fn can_match_price(&self, incoming: &Order, resting_price: Decimal) -> bool {
+        let max_deviation = self.config.max_market_deviation;
+        match (incoming.order_type, incoming.order_side) {
+            (OrderType::Market, _) if !incoming.price_protected => true,
+            (OrderType::Market, OrderSide::Buy) => {
+                resting_price <= incoming.price + incoming.price.abs() * max_deviation
+            }
+            (OrderType::Market, OrderSide::Sell) => {
+                resting_price >= incoming.price - incoming.price.abs() * max_deviation
             }
+            (OrderType::Stop { .. }, _) => true,
+            (OrderType::Limit | OrderType::StopLimit { .. }, OrderSide::Buy) => {
+                incoming.price >= resting_price
+            }
+            (OrderType::Limit | OrderType::StopLimit { .. }, OrderSide::Sell) => {
+                incoming.price <= resting_price
+            }
+        }
+    }
+
+    fn best_resting_order(
+        &self,
+        item_id: Uuid,
+        incoming_side: OrderSide,
+    ) -> Option<(Decimal, Uuid)> {
+        let book = self.books.get(item_id)?;
+        let (price, order_queue) = match incoming_side {
+            OrderSide::Buy => book.asks.iter().next()?,
+            OrderSide::Sell => book.bids.iter().next_back()?,
         };
 
-        let prices: Vec<Decimal> = match incoming_order.order_side {
-            OrderSide::Buy => price_maps.keys().cloned().collect(),
-            OrderSide::Sell => price_maps.keys().cloned().rev().collect(),
+        Some((*price, *order_queue.front()?))
+    }
+
+    fn matchable_quantity(&self, incoming_order: &Order) -> Decimal {
+        let price_map = match self.books.get(incoming_order.item_id) {
+            Some(book) => match incoming_order.order_side {
+                OrderSide::Buy => &book.asks,
+                OrderSide::Sell => &book.bids,
+            },
+            None => return Decimal::ZERO,
         };
 
-        let mut queue_orders: Vec<(Decimal, Uuid)> = Vec::new();
+        let levels: Box<dyn Iterator<Item = (&Decimal, &VecDeque<Uuid>)>> =
+            match incoming_order.order_side {
+                OrderSide::Buy => Box::new(price_map.iter()),
+                OrderSide::Sell => Box::new(price_map.iter().rev()),
+            };
+
+        let quantity_needed = incoming_order.quantity - incoming_order.quantity_filled;
+        let mut matchable = Decimal::ZERO;
+
+        for (price, order_queue) in levels {
+            if !self.can_match_price(incoming_order, *price) {
+                break;
+            }
+
+            for order_id in order_queue {
+                let resting_order = match self.get_order_by_id(*order_id) {
+                    Some(order) => order,
+                    None => continue,
+                };
+
+                if matches!(
+                    resting_order.status,
+                    OrderStatus::Cancelled | OrderStatus::Closed
+                ) {
+                    continue;
+                }
+
+                if self.is_expired(resting_order.expires_at)
+                    && matches!(
+                        resting_order.time_in_force,
+                        TimeInForce::DAY | TimeInForce::GTD(_)
+                    )
+                {
+                    continue;
+                }
+
+                if resting_order.user_id == incoming_order.user_id {
+                    match self.config.self_trade_prevention {
+                        SelfTradePrevention::Allow => {}
+                        SelfTradePrevention::CancelResting => continue,
+                        SelfTradePrevention::CancelIncoming | SelfTradePrevention::CancelBoth => {
+                            return matchable;
+                        }
+                    }
+                }
 
-        for price in prices {
-            let order_queue = price_maps.get(&price);
-            for order_id in order_queue.unwrap() {
-                queue_orders.push((price, *order_id));
+                matchable += resting_order.quantity - resting_order.quantity_filled;
+                if matchable >= quantity_needed {
+                    return matchable;
+                }
             }
         }
 
+        matchable
+    }
+
+    fn below_min_quantity(&self, order: &Order) -> bool {
+        order
+            .min_quantity
+            .is_some_and(|min_quantity| self.matchable_quantity(order) < min_quantity)
+    }
+
+    /// Matches `incoming_order` against the opposite side of its book. An
+    /// order that is still resting (for instance a stored order handed back
+    /// in) is left untouched rather than traded against itself or its queue.
+    pub fn execute_order_matching(&mut self, incoming_order: &mut Order) {
+        if self.is_resting(incoming_order.id) {
+            return;
+        }
+
+        self.match_order(incoming_order);
+    }
+
+    fn match_order(&mut self, incoming_order: &mut Order) {
+        if matches!(incoming_order.time_in_force, TimeInForce::FOK)
+            && self.matchable_quantity(incoming_order)
+                < incoming_order.quantity - incoming_order.quantity_filled
+        {
+            self.cancel(incoming_order.id);
+            return;
+        }
+
+        let initial_quantity_filled = incoming_order.quantity_filled;
         let mut trades: Vec<Trade> = Vec::new();
-        let mut staged_order_to_fill: HashMap<Uuid, Decimal> = HashMap::new();
+        let mut incoming_cancelled = false;
+        let circuit_breaker = self.config.circuit_breaker.and_then(|max_move| {
+            self.last_price(incoming_order.item_id)
+                .map(|reference_price| (reference_price, max_move))
+        });
+
+        while incoming_order.quantity_filled < incoming_order.quantity {
+            let (price, resting_order_id) =
+                match self.best_resting_order(incoming_order.item_id, incoming_order.order_side) {
+                    Some(best) => best,
+                    None => break,
+                };
 
-        for (price, order_id) in queue_orders {
-            let resting_order = self.get_order_by_id(order_id);
+            let resting_order = match self.get_order_by_id(resting_order_id) {
+                Some(order) => order.clone(),
+                None => break,
+            };
 
-            if !resting_order.is_some() {
+            if matches!(
+                resting_order.status,
+                OrderStatus::Cancelled | OrderStatus::Closed
+            ) {
+                self.remove_from_book(resting_order.id);
                 continue;
             }
 
-            let resting_order = resting_order.unwrap();
-
             if self.is_expired(resting_order.expires_at)
-                && matches!(resting_order.time_in_force, TimeInForce::DAY)
+                && matches!(
+                    resting_order.time_in_force,
+                    TimeInForce::DAY | TimeInForce::GTD(_)
+                )
             {
                 self.remove_from_book(resting_order.id);
                 continue;
             }
 
-            if !self.can_match_price(incoming_order, resting_order) {
+            if !self.can_match_price(incoming_order, price) {
                 break;
             }
 
-            let available_quantity = resting_order.quantity - resting_order.quantity_filled;
+            if resting_order.user_id == incoming_order.user_id {
+                match self.config.self_trade_prevention {
+                    SelfTradePrevention::Allow => {}
+                    SelfTradePrevention::CancelResting => {
+                        self.cancel(resting_order.id);
+                        continue;
+                    }
+                    SelfTradePrevention::CancelIncoming => {
+                        incoming_cancelled = true;
+                        break;
+                    }
+                    SelfTradePrevention::CancelBoth => {
+                        self.cancel(resting_order.id);
+                        incoming_cancelled = true;
+                        break;
+                    }
+                }
+            }
+
+            let available_quantity = min(
+                resting_order.visible_quantity,
+                resting_order.quantity - resting_order.quantity_filled,
+            );
             if available_quantity <= Decimal::ZERO {
+                self.remove_from_book(resting_order.id);
                 continue;
             }
 
+            let execution_price = self.execution_price(incoming_order, price);
+            if let Some((reference_price, max_move)) = circuit_breaker
+                && Self::breaches_band(execution_price, reference_price, max_move)
+            {
+                self.halt_item(incoming_order.item_id);
+                incoming_cancelled = true;
+                break;
+            }
+
             let quantity_to_match = incoming_order.quantity - incoming_order.quantity_filled;
             let trade_quantity = min(available_quantity, quantity_to_match);
 
-            let trade_id: Uuid = Uuid::new_v4();
-
-            trades.push(Trade {
-                id: trade_id,
-                buy_order_id: if matches!(incoming_order.order_side, OrderSide::Buy) {
-                    incoming_order.id
-                } else {
-                    resting_order.id
-                },
-                sell_order_id: if matches!(incoming_order.order_side, OrderSide::Sell) {
-                    incoming_order.id
-                } else {
-                    resting_order.id
-                },
-                item_id: incoming_order.item_id,
-                quantity: trade_quantity,
-                price,
-                timestamp: Utc::now(),
-            });
-
-            *staged_order_to_fill
-                .entry(resting_order.id)
-                .or_insert(Decimal::ZERO) += trade_quantity;
-            *staged_order_to_fill
-                .entry(incoming_order.id)
-                .or_insert(Decimal::ZERO) += trade_quantity;
+            let (buy_order_id, sell_order_id) = match incoming_order.order_side {
+                OrderSide::Buy => (incoming_order.id, resting_order.id),
+                OrderSide::Sell => (resting_order.id, incoming_order.id),
+            };
+            let trade = self.new_trade(
+                buy_order_id,
+                sell_order_id,
+                incoming_order.item_id,
+                execution_price,
+                trade_quantity,
+                incoming_order.seq,
+            );
+            self.emit_trade(&trade);
+            trades.push(trade);
 
+            self.fill_order(resting_order.id, trade_quantity);
             incoming_order.quantity_filled += trade_quantity;
-
-            if incoming_order.quantity_filled == incoming_order.quantity {
-                break;
-            }
         }
 
-        let mut unstaged_matched_orders = false;
-
-        if trades.len() > 0 && matches!(incoming_order.time_in_force, TimeInForce::IOC) {
-            self.update_order_quantity(incoming_order.id, incoming_order.quantity_filled);
-            self.update_order_status(incoming_order.id, OrderStatus::Closed);
+        let incoming_quantity_filled = incoming_order.quantity_filled - initial_quantity_filled;
+        if incoming_quantity_filled > Decimal::ZERO
+            && let Some(order) = self.fill_order(incoming_order.id, incoming_quantity_filled)
+            && let Some(display_quantity) = order.display_quantity
+        {
+            order.visible_quantity = min(display_quantity, order.quantity - order.quantity_filled);
         }
 
-        if matches!(incoming_order.time_in_force, TimeInForce::FOK)
-            && incoming_order.quantity_filled != incoming_order.quantity
+        let is_immediate = matches!(incoming_order.time_in_force, TimeInForce::IOC)
+            || matches!(incoming_order.order_type, OrderType::Market);
+        if incoming_cancelled
+            || (is_immediate && incoming_order.quantity_filled < incoming_order.quantity)
         {
-            self.cancel_order(incoming_order.id);
-            unstaged_matched_orders = true;
+            self.update_order_status(incoming_order.id, OrderStatus::Cancelled);
         }
 
-        if !unstaged_matched_orders {
-            for (order_id, trade_quantity) in staged_order_to_fill {
-                self.fill_order(order_id, trade_quantity);
-            }
-            self.trades.append(&mut trades);
-        }
+        let traded_price_range = trades.iter().fold(None, |range, trade| match range {
+            Some((lowest, highest)) => Some((min(lowest, trade.price), max(highest, trade.price))),
+            None => Some((trade.price, trade.price)),
+        });
+
+        self.record_trades(trades);
 
         if incoming_order.quantity_filled == incoming_order.quantity {
             self.remove_from_book(incoming_order.id);
+        } else if let Some(order) = self.get_order_by_id(incoming_order.id)
+            && matches!(
+                order.status,
+                OrderStatus::Open | OrderStatus::PartiallyFilled
+            )
+        {
+            self.add_to_book(incoming_order.id);
+        }
+
+        if let Some((lowest_price, highest_price)) = traded_price_range
+            && !self.is_halted(incoming_order.item_id)
+        {
+            self.trigger_stop_orders(incoming_order.item_id, lowest_price, highest_price);
         }
     }
 }
 
+impl Default for OrderBookService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,13 +1,45 @@
 #[cfg(test)]
 mod tests {
     use crate::components::{
-        dto::{CreateOrderRequest, OrderSide, OrderStatus, OrderType, TimeInForce},
+        clock::{Clock, MockClock},
+        config::{
+            FeeSchedule, MatchPricePolicy, OrderBookConfig, PrecisionConfig, PrecisionRounding,
+            SelfTradePrevention,
+        },
+        dto::{
+            CreateOrderRequest, MatchResult, OrderEvent, OrderSide, OrderStatus, OrderType,
+            TimeInForce,
+        },
+        errors::OrderError,
         services::OrderBookService,
     };
+    use chrono::{DateTime, Duration, Utc};
+    use proptest::prelude::*;
     use rust_decimal::Decimal;
-    use std::str::FromStr;
+    use std::{
+        str::FromStr,
+        sync::{Arc, Mutex},
+    };
     use uuid::Uuid;
 
+    fn limit_order_request(
+        item_id: Uuid,
+        order_side: OrderSide,
+        price: &str,
+        quantity: &str,
+    ) -> CreateOrderRequest {
+        CreateOrderRequest {
+            item_id,
+            user_id: Uuid::new_v4(),
+            order_side,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GTC,
+            price: Some(Decimal::from_str(price).unwrap()),
+            quantity: Decimal::from_str(quantity).unwrap(),
+            ..Default::default()
+        }
+    }
+
     #[test]
     fn should_add_order() {
         let mut order_book = OrderBookService::new();
@@ -16,14 +48,15 @@ mod tests {
             user_id: Uuid::new_v4(),
             order_side: OrderSide::Buy,
             order_type: OrderType::Limit,
-            price: Decimal::from_str("10.0").unwrap(),
+            price: Some(Decimal::from_str("10.0").unwrap()),
             time_in_force: TimeInForce::DAY,
             quantity: Decimal::from_str("100.0").unwrap(),
+            ..Default::default()
         };
         let order = order_book.add_order(create_order_request).unwrap();
         assert_eq!(order.quantity, Decimal::from_str("100.0").unwrap());
-        assert_eq!(matches!(order.order_side, OrderSide::Buy), true);
-        assert_eq!(matches!(order.status, OrderStatus::Open), true);
+        assert!(matches!(order.order_side, OrderSide::Buy));
+        assert!(matches!(order.status, OrderStatus::Open));
     }
 
     #[test]
@@ -35,8 +68,9 @@ mod tests {
             order_side: OrderSide::Sell,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("20.0").unwrap(),
+            price: Some(Decimal::from_str("20.0").unwrap()),
             quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
         };
         let order = order_book.add_order(create_order_request).unwrap();
         let fetched_order = order_book.get_order_by_id(order.id);
@@ -53,16 +87,14 @@ mod tests {
             order_side: OrderSide::Buy,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("15.0").unwrap(),
+            price: Some(Decimal::from_str("15.0").unwrap()),
             quantity: Decimal::from_str("100.0").unwrap(),
+            ..Default::default()
         };
         let order = order_book.add_order(create_order_request).unwrap();
         let updated_order = order_book.update_order_status(order.id, OrderStatus::Closed);
         assert!(updated_order.is_some());
-        assert_eq!(
-            matches!(updated_order.unwrap().status, OrderStatus::Closed),
-            true
-        );
+        assert!(matches!(updated_order.unwrap().status, OrderStatus::Closed));
     }
 
     #[test]
@@ -74,13 +106,14 @@ mod tests {
             order_side: OrderSide::Sell,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("25.0").unwrap(),
+            price: Some(Decimal::from_str("25.0").unwrap()),
             quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
         };
         let order = order_book.add_order(create_order_request).unwrap();
         let updated_order =
             order_book.update_order_quantity(order.id, Decimal::from_str("75.0").unwrap());
-        assert!(updated_order.is_some());
+        assert!(updated_order.is_ok());
         assert_eq!(
             updated_order.unwrap().quantity,
             Decimal::from_str("75.0").unwrap()
@@ -96,8 +129,9 @@ mod tests {
             order_side: OrderSide::Buy,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("30.0").unwrap(),
+            price: Some(Decimal::from_str("30.0").unwrap()),
             quantity: Decimal::from_str("100.0").unwrap(),
+            ..Default::default()
         };
         let order = order_book.add_order(create_order_request).unwrap();
         order_book.cancel_order(order.id);
@@ -108,6 +142,54 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn should_return_cancelled_order_from_cancel_order_get() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+        let buy_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "20.0"))
+            .unwrap();
+
+        let cancelled_order = order_book.cancel_order_get(buy_order.id).unwrap();
+
+        assert_eq!(cancelled_order.id, buy_order.id);
+        assert!(matches!(cancelled_order.status, OrderStatus::Cancelled));
+        assert_eq!(
+            cancelled_order.quantity_filled,
+            Decimal::from_str("5.0").unwrap()
+        );
+        assert_eq!(order_book.best_bid(item_id), None);
+        assert!(order_book.cancel_order_get(Uuid::new_v4()).is_none());
+        assert!(order_book.cancel_order_get(buy_order.id).is_none());
+    }
+
+    #[test]
+    fn should_not_cancel_closed_order() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            record_commands: true,
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+        let sell_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+        let command_count = order_book.commands().len();
+
+        assert!(order_book.cancel_order_get(sell_order.id).is_none());
+        assert!(!order_book.cancel_order(sell_order.id));
+        assert!(matches!(
+            order_book.get_order_by_id(sell_order.id).unwrap().status,
+            OrderStatus::Closed
+        ));
+        assert_eq!(order_book.commands().len(), command_count);
+    }
+
     #[test]
     fn should_be_partially_filled() {
         let mut order_book = OrderBookService::new();
@@ -119,8 +201,9 @@ mod tests {
             order_side: OrderSide::Buy,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("10.0").unwrap(),
+            price: Some(Decimal::from_str("10.0").unwrap()),
             quantity: Decimal::from_str("100.0").unwrap(),
+            ..Default::default()
         };
         let buy_order = order_book.add_order(buy_order_request).unwrap();
 
@@ -130,8 +213,9 @@ mod tests {
             order_side: OrderSide::Sell,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("10.0").unwrap(),
+            price: Some(Decimal::from_str("10.0").unwrap()),
             quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
         };
         let sell_order = order_book.add_order(sell_order_request).unwrap();
 
@@ -146,14 +230,11 @@ mod tests {
             fetched_sell_order.quantity_filled,
             Decimal::from_str("50.0").unwrap()
         );
-        assert_eq!(
-            matches!(fetched_buy_order.status, OrderStatus::PartiallyFilled),
-            true
-        );
-        assert_eq!(
-            matches!(fetched_sell_order.status, OrderStatus::Closed),
-            true
-        );
+        assert!(matches!(
+            fetched_buy_order.status,
+            OrderStatus::PartiallyFilled
+        ));
+        assert!(matches!(fetched_sell_order.status, OrderStatus::Closed));
     }
 
     #[test]
@@ -167,8 +248,9 @@ mod tests {
             order_side: OrderSide::Buy,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("10.0").unwrap(),
+            price: Some(Decimal::from_str("10.0").unwrap()),
             quantity: Decimal::from_str("100.0").unwrap(),
+            ..Default::default()
         };
         let buy_order = order_book.add_order(buy_order_request).unwrap();
 
@@ -178,8 +260,9 @@ mod tests {
             order_side: OrderSide::Sell,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("10.0").unwrap(),
+            price: Some(Decimal::from_str("10.0").unwrap()),
             quantity: Decimal::from_str("100.0").unwrap(),
+            ..Default::default()
         };
         let sell_order = order_book.add_order(sell_order_request).unwrap();
 
@@ -194,14 +277,8 @@ mod tests {
             fetched_sell_order.quantity_filled,
             Decimal::from_str("100.0").unwrap()
         );
-        assert_eq!(
-            matches!(fetched_buy_order.status, OrderStatus::Closed),
-            true
-        );
-        assert_eq!(
-            matches!(fetched_sell_order.status, OrderStatus::Closed),
-            true
-        );
+        assert!(matches!(fetched_buy_order.status, OrderStatus::Closed));
+        assert!(matches!(fetched_sell_order.status, OrderStatus::Closed));
     }
 
     #[test]
@@ -213,8 +290,9 @@ mod tests {
             order_side: OrderSide::Buy,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("10.0").unwrap(),
+            price: Some(Decimal::from_str("10.0").unwrap()),
             quantity: Decimal::from_str("100.0").unwrap(),
+            ..Default::default()
         };
         let order = order_book.add_order(create_order_request).unwrap();
         let updated_order =
@@ -226,6 +304,107 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_reject_invalid_price_update() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book.set_tick_size(item_id, Decimal::from_str("0.5").unwrap());
+        let order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+
+        for price in ["10.2", "-1.0"] {
+            assert!(
+                order_book
+                    .update_order_price(order.id, Decimal::from_str(price).unwrap())
+                    .is_none()
+            );
+        }
+        assert_eq!(
+            order_book.get_order_by_id(order.id).unwrap().price,
+            Decimal::from_str("10.0").unwrap()
+        );
+        assert_eq!(
+            order_book.best_bid(item_id),
+            Some(Decimal::from_str("10.0").unwrap())
+        );
+    }
+
+    #[test]
+    fn should_not_reprice_inactive_orders() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            record_commands: true,
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+        let closed_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+        let cancelled_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "5.0"))
+            .unwrap();
+        order_book.cancel_order(cancelled_order.id);
+        let command_count = order_book.commands().len();
+
+        for order_id in [closed_order.id, cancelled_order.id] {
+            assert!(
+                order_book
+                    .update_order_price(order_id, Decimal::from_str("12.0").unwrap())
+                    .is_none()
+            );
+        }
+        assert_eq!(
+            order_book.get_order_by_id(closed_order.id).unwrap().price,
+            Decimal::from_str("10.0").unwrap()
+        );
+        assert_eq!(
+            order_book
+                .get_order_by_id(cancelled_order.id)
+                .unwrap()
+                .price,
+            Decimal::from_str("9.0").unwrap()
+        );
+        assert_eq!(order_book.commands().len(), command_count);
+    }
+
+    #[test]
+    fn should_apply_precision_and_size_limits_to_amendments() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            precision: Some(PrecisionConfig {
+                price_dp: 1,
+                size_dp: 1,
+                rounding: PrecisionRounding::Reject,
+            }),
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+        order_book.set_size_limits(item_id, None, Some(Decimal::from_str("10.0").unwrap()));
+        let order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+
+        assert!(
+            order_book
+                .update_order_price(order.id, Decimal::from_str("10.05").unwrap())
+                .is_none()
+        );
+        assert!(matches!(
+            order_book.amend_order(order.id, Some(Decimal::from_str("10.05").unwrap()), None),
+            Err(OrderError::ExcessPrecision { .. })
+        ));
+        assert!(matches!(
+            order_book.amend_order(order.id, None, Some(Decimal::from_str("20.0").unwrap())),
+            Err(OrderError::QuantityAboveMaximum { .. })
+        ));
+
+        let order = order_book.get_order_by_id(order.id).unwrap();
+        assert_eq!(order.price, Decimal::from_str("10.0").unwrap());
+        assert_eq!(order.quantity, Decimal::from_str("5.0").unwrap());
+    }
+
     #[test]
     fn trades_should_contain_filled_orders() {
         let mut order_book = OrderBookService::new();
@@ -237,8 +416,9 @@ mod tests {
             order_side: OrderSide::Buy,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("10.0").unwrap(),
+            price: Some(Decimal::from_str("10.0").unwrap()),
             quantity: Decimal::from_str("100.0").unwrap(),
+            ..Default::default()
         };
         let buy_order = order_book.add_order(buy_order_request).unwrap();
 
@@ -248,8 +428,9 @@ mod tests {
             order_side: OrderSide::Sell,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("10.0").unwrap(),
+            price: Some(Decimal::from_str("10.0").unwrap()),
             quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
         };
         let sell_order = order_book.add_order(sell_order_request).unwrap();
 
@@ -268,13 +449,14 @@ mod tests {
             order_side: OrderSide::Sell,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("20.0").unwrap(),
+            price: Some(Decimal::from_str("20.0").unwrap()),
             quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
         };
         let order = order_book.add_order(create_order_request).unwrap();
         let updated_order =
             order_book.update_order_quantity(order.id, Decimal::from_str("75.0").unwrap());
-        assert!(updated_order.is_some());
+        assert!(updated_order.is_ok());
         assert_eq!(
             updated_order.unwrap().quantity,
             Decimal::from_str("75.0").unwrap()
@@ -299,8 +481,9 @@ mod tests {
             order_side: OrderSide::Buy,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("10.0").unwrap(),
+            price: Some(Decimal::from_str("10.0").unwrap()),
             quantity: Decimal::from_str("100.0").unwrap(),
+            ..Default::default()
         };
 
         let buy_order = order_book.add_order(buy_order_request).unwrap();
@@ -310,8 +493,9 @@ mod tests {
             order_side: OrderSide::Sell,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("15.0").unwrap(),
+            price: Some(Decimal::from_str("15.0").unwrap()),
             quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
         };
 
         let sell_order = order_book.add_order(sell_order_request).unwrap();
@@ -320,8 +504,8 @@ mod tests {
 
         assert_eq!(fetched_buy_order.quantity_filled, Decimal::ZERO);
         assert_eq!(fetched_sell_order.quantity_filled, Decimal::ZERO);
-        assert_eq!(matches!(fetched_buy_order.status, OrderStatus::Open), true);
-        assert_eq!(matches!(fetched_sell_order.status, OrderStatus::Open), true);
+        assert!(matches!(fetched_buy_order.status, OrderStatus::Open));
+        assert!(matches!(fetched_sell_order.status, OrderStatus::Open));
     }
 
     #[test]
@@ -333,15 +517,12 @@ mod tests {
             order_side: OrderSide::Buy,
             order_type: OrderType::Market,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::ZERO,
+            price: None,
             quantity: Decimal::from_str("100.0").unwrap(),
+            ..Default::default()
         };
         let result = order_book.add_order(create_order_request);
-        assert!(result.is_err());
-        assert_eq!(
-            result.err().unwrap(),
-            "Market order cannot be placed without any existing orders to determine price"
-        );
+        assert!(matches!(result, Err(OrderError::MarketNoLiquidity)));
     }
 
     #[test]
@@ -355,8 +536,9 @@ mod tests {
             order_side: OrderSide::Sell,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("10.0").unwrap(),
+            price: Some(Decimal::from_str("10.0").unwrap()),
             quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
         };
         let _ = order_book.add_order(sell_order_request);
         let current_market_price = order_book
@@ -369,8 +551,9 @@ mod tests {
             order_side: OrderSide::Buy,
             order_type: OrderType::Market,
             time_in_force: TimeInForce::DAY,
-            price: current_market_price,
+            price: Some(current_market_price),
             quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
         };
         let buy_market_order = order_book.add_order(buy_market_order_request).unwrap();
 
@@ -392,8 +575,9 @@ mod tests {
             order_side: OrderSide::Sell,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("10.0").unwrap(),
+            price: Some(Decimal::from_str("10.0").unwrap()),
             quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
         };
         let _ = order_book.add_order(sell_order_request);
 
@@ -403,16 +587,17 @@ mod tests {
             order_side: OrderSide::Buy,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::IOC,
-            price: Decimal::from_str("10.0").unwrap(),
+            price: Some(Decimal::from_str("10.0").unwrap()),
             quantity: Decimal::from_str("100.0").unwrap(),
+            ..Default::default()
         };
         let buy_ioc_order = order_book.add_order(buy_ioc_order_request).unwrap();
         assert_eq!(
             buy_ioc_order.quantity_filled,
             Decimal::from_str("50.0").unwrap()
         );
-        assert_eq!(buy_ioc_order.quantity, Decimal::from_str("50.0").unwrap());
-        assert_eq!(matches!(buy_ioc_order.status, OrderStatus::Closed), true);
+        assert_eq!(buy_ioc_order.quantity, Decimal::from_str("100.0").unwrap());
+        assert!(matches!(buy_ioc_order.status, OrderStatus::Cancelled));
     }
 
     #[test]
@@ -426,8 +611,9 @@ mod tests {
             order_side: OrderSide::Sell,
             order_type: OrderType::Limit,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("30.0").unwrap(),
+            price: Some(Decimal::from_str("30.0").unwrap()),
             quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
         };
         let _ = order_book.add_order(sell_order_request);
 
@@ -437,15 +623,5748 @@ mod tests {
             order_side: OrderSide::Buy,
             order_type: OrderType::Market,
             time_in_force: TimeInForce::DAY,
-            price: Decimal::from_str("20.0").unwrap(),
+            price: Some(Decimal::from_str("20.0").unwrap()),
             quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
         };
         let result = order_book.add_order(buy_market_order_request);
         assert!(result.is_err());
 
-        let err_msg = result.err().unwrap();
-        assert!(err_msg.contains("Market order price cannot be more than 5% away"));
-        assert!(err_msg.contains("30"));
-        assert!(err_msg.contains("20"));
+        let error = result.err().unwrap();
+        assert!(matches!(
+            error,
+            OrderError::MarketPriceTooFar { market, order, .. }
+                if market == Decimal::from_str("30.0").unwrap()
+                    && order == Decimal::from_str("20.0").unwrap()
+        ));
+        assert!(
+            error
+                .to_string()
+                .contains("Market order price cannot be more than 5% away")
+        );
+    }
+
+    #[test]
+    fn should_fill_same_price_orders_in_time_priority() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let mut sell_orders = Vec::new();
+        for _ in 0..3 {
+            let sell_order_request = CreateOrderRequest {
+                item_id,
+                user_id: Uuid::new_v4(),
+                order_side: OrderSide::Sell,
+                order_type: OrderType::Limit,
+                time_in_force: TimeInForce::GTC,
+                price: Some(Decimal::from_str("10.0").unwrap()),
+                quantity: Decimal::from_str("50.0").unwrap(),
+                ..Default::default()
+            };
+            sell_orders.push(order_book.add_order(sell_order_request).unwrap());
+        }
+        assert!(sell_orders[0].created_at <= sell_orders[1].created_at);
+        assert!(sell_orders[1].created_at <= sell_orders[2].created_at);
+
+        let buy_order_request = CreateOrderRequest {
+            item_id,
+            user_id: Uuid::new_v4(),
+            order_side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GTC,
+            price: Some(Decimal::from_str("10.0").unwrap()),
+            quantity: Decimal::from_str("120.0").unwrap(),
+            ..Default::default()
+        };
+        let buy_order = order_book.add_order(buy_order_request).unwrap();
+        assert!(matches!(buy_order.status, OrderStatus::Closed));
+
+        assert_eq!(order_book.trades.len(), 3);
+        for (trade, sell_order) in order_book.trades.iter().zip(&sell_orders) {
+            assert_eq!(trade.sell_order_id, sell_order.id);
+        }
+
+        let first = order_book.get_order_by_id(sell_orders[0].id).unwrap();
+        let second = order_book.get_order_by_id(sell_orders[1].id).unwrap();
+        let third = order_book.get_order_by_id(sell_orders[2].id).unwrap();
+        assert!(matches!(first.status, OrderStatus::Closed));
+        assert!(matches!(second.status, OrderStatus::Closed));
+        assert!(matches!(third.status, OrderStatus::PartiallyFilled));
+        assert_eq!(third.quantity_filled, Decimal::from_str("20.0").unwrap());
+    }
+
+    #[test]
+    fn should_fill_better_price_before_earlier_order() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let worse_sell_request = CreateOrderRequest {
+            item_id,
+            user_id: Uuid::new_v4(),
+            order_side: OrderSide::Sell,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GTC,
+            price: Some(Decimal::from_str("11.0").unwrap()),
+            quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
+        };
+        let worse_sell = order_book.add_order(worse_sell_request).unwrap();
+
+        let better_sell_request = CreateOrderRequest {
+            item_id,
+            user_id: Uuid::new_v4(),
+            order_side: OrderSide::Sell,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GTC,
+            price: Some(Decimal::from_str("10.0").unwrap()),
+            quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
+        };
+        let better_sell = order_book.add_order(better_sell_request).unwrap();
+
+        let buy_order_request = CreateOrderRequest {
+            item_id,
+            user_id: Uuid::new_v4(),
+            order_side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GTC,
+            price: Some(Decimal::from_str("11.0").unwrap()),
+            quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
+        };
+        let _ = order_book.add_order(buy_order_request).unwrap();
+
+        assert_eq!(order_book.trades.len(), 1);
+        assert_eq!(order_book.trades[0].sell_order_id, better_sell.id);
+        assert_eq!(
+            order_book.trades[0].price,
+            Decimal::from_str("10.0").unwrap()
+        );
+        let fetched_worse_sell = order_book.get_order_by_id(worse_sell.id).unwrap();
+        assert!(matches!(fetched_worse_sell.status, OrderStatus::Open));
+    }
+
+    #[test]
+    fn should_not_match_cancelled_resting_order() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let sell_order_request = CreateOrderRequest {
+            item_id,
+            user_id: Uuid::new_v4(),
+            order_side: OrderSide::Sell,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GTC,
+            price: Some(Decimal::from_str("10.0").unwrap()),
+            quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
+        };
+        let sell_order = order_book.add_order(sell_order_request).unwrap();
+        assert!(order_book.cancel_order(sell_order.id));
+        assert!(
+            order_book
+                .get_current_market_price(item_id, OrderSide::Buy)
+                .is_none()
+        );
+
+        let buy_order_request = CreateOrderRequest {
+            item_id,
+            user_id: Uuid::new_v4(),
+            order_side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GTC,
+            price: Some(Decimal::from_str("10.0").unwrap()),
+            quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
+        };
+        let buy_order = order_book.add_order(buy_order_request).unwrap();
+
+        assert!(order_book.trades.is_empty());
+        assert_eq!(buy_order.quantity_filled, Decimal::ZERO);
+        assert!(matches!(buy_order.status, OrderStatus::Open));
+    }
+
+    #[test]
+    fn should_skip_resting_order_marked_cancelled_by_status_update() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let sell_order_request = CreateOrderRequest {
+            item_id,
+            user_id: Uuid::new_v4(),
+            order_side: OrderSide::Sell,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GTC,
+            price: Some(Decimal::from_str("10.0").unwrap()),
+            quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
+        };
+        let sell_order = order_book.add_order(sell_order_request).unwrap();
+        order_book.update_order_status(sell_order.id, OrderStatus::Cancelled);
+
+        let buy_order_request = CreateOrderRequest {
+            item_id,
+            user_id: Uuid::new_v4(),
+            order_side: OrderSide::Buy,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GTC,
+            price: Some(Decimal::from_str("10.0").unwrap()),
+            quantity: Decimal::from_str("50.0").unwrap(),
+            ..Default::default()
+        };
+        let _ = order_book.add_order(buy_order_request).unwrap();
+
+        assert!(order_book.trades.is_empty());
+        assert!(
+            order_book
+                .get_current_market_price(item_id, OrderSide::Buy)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn should_aggregate_depth_by_price_level() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        for (side, price, quantity) in [
+            (OrderSide::Buy, "9.0", "10.0"),
+            (OrderSide::Buy, "10.0", "20.0"),
+            (OrderSide::Buy, "10.0", "5.0"),
+            (OrderSide::Sell, "12.0", "15.0"),
+            (OrderSide::Sell, "11.0", "30.0"),
+            (OrderSide::Sell, "12.0", "25.0"),
+        ] {
+            order_book
+                .add_order(limit_order_request(item_id, side, price, quantity))
+                .unwrap();
+        }
+
+        let depth = order_book.depth(item_id, 10);
+        assert_eq!(
+            depth.bids,
+            vec![
+                (
+                    Decimal::from_str("10.0").unwrap(),
+                    Decimal::from_str("25.0").unwrap()
+                ),
+                (
+                    Decimal::from_str("9.0").unwrap(),
+                    Decimal::from_str("10.0").unwrap()
+                ),
+            ]
+        );
+        assert_eq!(
+            depth.asks,
+            vec![
+                (
+                    Decimal::from_str("11.0").unwrap(),
+                    Decimal::from_str("30.0").unwrap()
+                ),
+                (
+                    Decimal::from_str("12.0").unwrap(),
+                    Decimal::from_str("40.0").unwrap()
+                ),
+            ]
+        );
+
+        let truncated_depth = order_book.depth(item_id, 1);
+        assert_eq!(truncated_depth.bids.len(), 1);
+        assert_eq!(truncated_depth.asks.len(), 1);
+        assert_eq!(
+            truncated_depth.bids[0].0,
+            Decimal::from_str("10.0").unwrap()
+        );
+        assert_eq!(
+            truncated_depth.asks[0].0,
+            Decimal::from_str("11.0").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_report_remaining_quantity_in_depth() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "50.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "20.0"))
+            .unwrap();
+
+        let depth = order_book.depth(item_id, 5);
+        assert!(depth.bids.is_empty());
+        assert_eq!(
+            depth.asks,
+            vec![(
+                Decimal::from_str("10.0").unwrap(),
+                Decimal::from_str("30.0").unwrap()
+            )]
+        );
+        assert!(order_book.depth(Uuid::new_v4(), 5).asks.is_empty());
+    }
+
+    #[test]
+    fn should_show_only_display_quantity_of_iceberg_in_depth() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let iceberg_order = order_book
+            .add_order(CreateOrderRequest {
+                display_quantity: Some(Decimal::from_str("100.0").unwrap()),
+                ..limit_order_request(item_id, OrderSide::Sell, "10.0", "1000.0")
+            })
+            .unwrap();
+        assert_eq!(
+            iceberg_order.visible_quantity,
+            Decimal::from_str("100.0").unwrap()
+        );
+
+        let depth = order_book.depth(item_id, 5);
+        assert_eq!(
+            depth.asks,
+            vec![(
+                Decimal::from_str("10.0").unwrap(),
+                Decimal::from_str("100.0").unwrap()
+            )]
+        );
+    }
+
+    #[test]
+    fn should_replenish_iceberg_and_lose_time_priority() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let iceberg_order = order_book
+            .add_order(CreateOrderRequest {
+                display_quantity: Some(Decimal::from_str("100.0").unwrap()),
+                ..limit_order_request(item_id, OrderSide::Sell, "10.0", "1000.0")
+            })
+            .unwrap();
+        let later_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "50.0",
+            ))
+            .unwrap();
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Buy,
+                "10.0",
+                "100.0",
+            ))
+            .unwrap();
+        assert_eq!(order_book.trades.len(), 1);
+        assert_eq!(order_book.trades[0].sell_order_id, iceberg_order.id);
+
+        let replenished_order = order_book.get_order_by_id(iceberg_order.id).unwrap();
+        assert_eq!(
+            replenished_order.quantity_filled,
+            Decimal::from_str("100.0").unwrap()
+        );
+        assert_eq!(
+            replenished_order.visible_quantity,
+            Decimal::from_str("100.0").unwrap()
+        );
+        assert_eq!(
+            order_book.depth(item_id, 5).asks,
+            vec![(
+                Decimal::from_str("10.0").unwrap(),
+                Decimal::from_str("150.0").unwrap()
+            )]
+        );
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Buy,
+                "10.0",
+                "100.0",
+            ))
+            .unwrap();
+        assert_eq!(order_book.trades.len(), 3);
+        assert_eq!(order_book.trades[1].sell_order_id, later_order.id);
+        assert_eq!(
+            order_book.trades[1].quantity,
+            Decimal::from_str("50.0").unwrap()
+        );
+        assert_eq!(order_book.trades[2].sell_order_id, iceberg_order.id);
+        assert_eq!(
+            order_book.trades[2].quantity,
+            Decimal::from_str("50.0").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_fill_multiple_iceberg_slices_in_one_pass() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let iceberg_order = order_book
+            .add_order(CreateOrderRequest {
+                display_quantity: Some(Decimal::from_str("100.0").unwrap()),
+                ..limit_order_request(item_id, OrderSide::Sell, "10.0", "1000.0")
+            })
+            .unwrap();
+
+        let buy_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Buy,
+                "10.0",
+                "250.0",
+            ))
+            .unwrap();
+        assert!(matches!(buy_order.status, OrderStatus::Closed));
+
+        let trade_quantities: Vec<Decimal> = order_book
+            .trades
+            .iter()
+            .map(|trade| trade.quantity)
+            .collect();
+        assert_eq!(
+            trade_quantities,
+            vec![
+                Decimal::from_str("100.0").unwrap(),
+                Decimal::from_str("100.0").unwrap(),
+                Decimal::from_str("50.0").unwrap(),
+            ]
+        );
+
+        let fetched_iceberg_order = order_book.get_order_by_id(iceberg_order.id).unwrap();
+        assert_eq!(
+            fetched_iceberg_order.visible_quantity,
+            Decimal::from_str("50.0").unwrap()
+        );
+        assert_eq!(
+            order_book.depth(item_id, 5).asks,
+            vec![(
+                Decimal::from_str("10.0").unwrap(),
+                Decimal::from_str("50.0").unwrap()
+            )]
+        );
+    }
+
+    #[test]
+    fn should_hide_dark_order_from_depth_but_still_fill_it() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let hidden_order = order_book
+            .add_order(CreateOrderRequest {
+                hidden: true,
+                ..limit_order_request(item_id, OrderSide::Sell, "10.0", "30.0")
+            })
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "11.0",
+                "20.0",
+            ))
+            .unwrap();
+
+        let depth = order_book.depth(item_id, 5);
+        assert_eq!(
+            depth.asks,
+            vec![(
+                Decimal::from_str("11.0").unwrap(),
+                Decimal::from_str("20.0").unwrap()
+            )]
+        );
+        assert!(
+            order_book
+                .level2(item_id, OrderSide::Sell, 5)
+                .iter()
+                .all(|order| order.id != hidden_order.id)
+        );
+        assert_eq!(order_book.stats(item_id).ask_levels, 1);
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "10.0"))
+            .unwrap();
+
+        assert_eq!(order_book.trades.len(), 1);
+        assert_eq!(order_book.trades[0].sell_order_id, hidden_order.id);
+        assert_eq!(
+            order_book.trades[0].price,
+            Decimal::from_str("10.0").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_fill_visible_order_before_hidden_order_at_same_price() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let hidden_order = order_book
+            .add_order(CreateOrderRequest {
+                hidden: true,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "10.0")
+            })
+            .unwrap();
+        let visible_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "10.0"))
+            .unwrap();
+
+        let priority: Vec<Uuid> = order_book
+            .resting_orders(item_id, OrderSide::Buy)
+            .map(|order| order.id)
+            .collect();
+        assert_eq!(priority, vec![visible_order.id, hidden_order.id]);
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "15.0",
+            ))
+            .unwrap();
+
+        assert_eq!(order_book.trades[0].buy_order_id, visible_order.id);
+        assert_eq!(order_book.trades[1].buy_order_id, hidden_order.id);
+        assert_eq!(
+            order_book.trades[1].quantity,
+            Decimal::from_str("5.0").unwrap()
+        );
+        assert!(order_book.depth(item_id, 5).bids.is_empty());
+    }
+
+    #[test]
+    fn should_leave_hidden_only_levels_out_of_best_prices() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(CreateOrderRequest {
+                hidden: true,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0")
+            })
+            .unwrap();
+        assert_eq!(order_book.best_bid(item_id), None);
+        assert_eq!(order_book.best_level(item_id, OrderSide::Buy), None);
+
+        let visible_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "3.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "12.0", "3.0"))
+            .unwrap();
+
+        assert_eq!(
+            order_book.best_bid(item_id),
+            Some(Decimal::from_str("9.0").unwrap())
+        );
+        assert_eq!(
+            order_book.best_level(item_id, OrderSide::Buy),
+            Some((
+                Decimal::from_str("9.0").unwrap(),
+                Decimal::from_str("3.0").unwrap()
+            ))
+        );
+        assert_eq!(
+            order_book.spread(item_id),
+            Some(Decimal::from_str("3.0").unwrap())
+        );
+
+        order_book.cancel_order(visible_order.id);
+        order_book
+            .add_order(CreateOrderRequest {
+                order_type: OrderType::Market,
+                price: None,
+                ..limit_order_request(item_id, OrderSide::Sell, "0.0", "2.0")
+            })
+            .unwrap();
+
+        assert_eq!(
+            order_book.trades[0].price,
+            Decimal::from_str("10.0").unwrap()
+        );
+        assert_eq!(order_book.best_bid(item_id), None);
+        assert_eq!(order_book.mid_price(item_id), None);
+    }
+
+    #[test]
+    fn should_build_requests_from_string_and_float_amounts() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let sell_request =
+            CreateOrderRequest::limit(item_id, Uuid::new_v4(), OrderSide::Sell, "10.25", "5")
+                .unwrap();
+        assert!(matches!(sell_request.order_type, OrderType::Limit));
+        assert_eq!(
+            sell_request.price,
+            Some(Decimal::from_str("10.25").unwrap())
+        );
+        assert_eq!(sell_request.quantity, Decimal::from_str("5").unwrap());
+        order_book.add_order(sell_request).unwrap();
+
+        let buy_request =
+            CreateOrderRequest::market(item_id, Uuid::new_v4(), OrderSide::Buy, 2.5).unwrap();
+        assert_eq!(buy_request.price, None);
+        let buy_order = order_book.add_order(buy_request).unwrap();
+
+        assert!(matches!(buy_order.status, OrderStatus::Closed));
+        assert_eq!(
+            order_book.trades[0].quantity,
+            Decimal::from_str("2.5").unwrap()
+        );
+        assert!(
+            CreateOrderRequest::limit(item_id, Uuid::new_v4(), OrderSide::Buy, "ten", "5").is_err()
+        );
+        assert!(
+            CreateOrderRequest::market(item_id, Uuid::new_v4(), OrderSide::Buy, f64::NAN).is_err()
+        );
+    }
+
+    #[test]
+    fn should_reject_non_positive_display_quantity() {
+        let mut order_book = OrderBookService::new();
+        let result = order_book.add_order(CreateOrderRequest {
+            display_quantity: Some(Decimal::ZERO),
+            ..limit_order_request(Uuid::new_v4(), OrderSide::Sell, "10.0", "100.0")
+        });
+        assert!(matches!(
+            result,
+            Err(OrderError::NonPositiveDisplayQuantity)
+        ));
+    }
+
+    #[test]
+    fn should_trigger_buy_stop_on_upward_trade() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "10.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "12.0",
+                "50.0",
+            ))
+            .unwrap();
+
+        let stop_order = order_book
+            .add_order(CreateOrderRequest {
+                order_type: OrderType::Stop {
+                    trigger: Decimal::from_str("10.0").unwrap(),
+                },
+                ..limit_order_request(item_id, OrderSide::Buy, "0.0", "20.0")
+            })
+            .unwrap();
+        assert!(matches!(stop_order.status, OrderStatus::Open));
+        assert!(order_book.trades.is_empty());
+        assert!(order_book.depth(item_id, 5).bids.is_empty());
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "10.0"))
+            .unwrap();
+
+        assert_eq!(order_book.trades.len(), 2);
+        let stop_trade = &order_book.trades[1];
+        assert_eq!(stop_trade.buy_order_id, stop_order.id);
+        assert_eq!(stop_trade.price, Decimal::from_str("12.0").unwrap());
+        assert_eq!(stop_trade.quantity, Decimal::from_str("20.0").unwrap());
+
+        let activated_order = order_book.get_order_by_id(stop_order.id).unwrap();
+        assert!(matches!(activated_order.order_type, OrderType::Market));
+        assert!(matches!(activated_order.status, OrderStatus::Closed));
+    }
+
+    #[test]
+    fn should_trigger_sell_stop_limit_on_downward_trade() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "10.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "8.0", "50.0"))
+            .unwrap();
+
+        let stop_limit_order = order_book
+            .add_order(CreateOrderRequest {
+                order_type: OrderType::StopLimit {
+                    trigger: Decimal::from_str("10.0").unwrap(),
+                    limit: Decimal::from_str("8.0").unwrap(),
+                },
+                ..limit_order_request(item_id, OrderSide::Sell, "0.0", "20.0")
+            })
+            .unwrap();
+        assert!(order_book.depth(item_id, 5).asks.is_empty());
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "10.0",
+            ))
+            .unwrap();
+
+        assert_eq!(order_book.trades.len(), 2);
+        let stop_trade = &order_book.trades[1];
+        assert_eq!(stop_trade.sell_order_id, stop_limit_order.id);
+        assert_eq!(stop_trade.price, Decimal::from_str("8.0").unwrap());
+        assert_eq!(stop_trade.quantity, Decimal::from_str("20.0").unwrap());
+
+        let activated_order = order_book.get_order_by_id(stop_limit_order.id).unwrap();
+        assert!(matches!(activated_order.order_type, OrderType::Limit));
+        assert!(matches!(activated_order.status, OrderStatus::Closed));
+    }
+
+    #[test]
+    fn should_not_trigger_stop_before_trigger_price() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "10.0",
+            ))
+            .unwrap();
+        let stop_order = order_book
+            .add_order(CreateOrderRequest {
+                order_type: OrderType::Stop {
+                    trigger: Decimal::from_str("11.0").unwrap(),
+                },
+                ..limit_order_request(item_id, OrderSide::Buy, "0.0", "20.0")
+            })
+            .unwrap();
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+
+        assert_eq!(order_book.trades.len(), 1);
+        let pending_order = order_book.get_order_by_id(stop_order.id).unwrap();
+        assert!(matches!(pending_order.order_type, OrderType::Stop { .. }));
+        assert_eq!(pending_order.quantity_filled, Decimal::ZERO);
+    }
+
+    #[test]
+    fn should_list_untriggered_stops_as_pending_orders() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "10.0"))
+            .unwrap();
+        assert!(order_book.pending_orders(item_id).is_empty());
+
+        let stop_order = order_book
+            .add_order(CreateOrderRequest {
+                order_type: OrderType::Stop {
+                    trigger: Decimal::from_str("11.0").unwrap(),
+                },
+                ..limit_order_request(item_id, OrderSide::Buy, "0.0", "20.0")
+            })
+            .unwrap();
+
+        let pending_ids: Vec<Uuid> = order_book
+            .pending_orders(item_id)
+            .iter()
+            .map(|order| order.id)
+            .collect();
+        assert_eq!(pending_ids, vec![stop_order.id]);
+        assert!(order_book.pending_orders(Uuid::new_v4()).is_empty());
+
+        order_book.cancel_order(stop_order.id);
+        assert!(order_book.pending_orders(item_id).is_empty());
+    }
+
+    fn self_trade_scenario(
+        self_trade_prevention: SelfTradePrevention,
+    ) -> (OrderBookService, Uuid, Uuid) {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            self_trade_prevention,
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+
+        let resting_order = order_book
+            .add_order(CreateOrderRequest {
+                user_id,
+                ..limit_order_request(item_id, OrderSide::Sell, "10.0", "50.0")
+            })
+            .unwrap();
+        let incoming_order = order_book
+            .add_order(CreateOrderRequest {
+                user_id,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "50.0")
+            })
+            .unwrap();
+
+        (order_book, resting_order.id, incoming_order.id)
+    }
+
+    #[test]
+    fn should_allow_self_trade_by_default() {
+        let (order_book, resting_id, incoming_id) = self_trade_scenario(SelfTradePrevention::Allow);
+
+        assert_eq!(order_book.trades.len(), 1);
+        let resting_order = order_book.get_order_by_id(resting_id).unwrap();
+        let incoming_order = order_book.get_order_by_id(incoming_id).unwrap();
+        assert!(matches!(resting_order.status, OrderStatus::Closed));
+        assert!(matches!(incoming_order.status, OrderStatus::Closed));
+    }
+
+    #[test]
+    fn should_cancel_resting_order_on_self_trade() {
+        let (order_book, resting_id, incoming_id) =
+            self_trade_scenario(SelfTradePrevention::CancelResting);
+
+        assert!(order_book.trades.is_empty());
+        let resting_order = order_book.get_order_by_id(resting_id).unwrap();
+        let incoming_order = order_book.get_order_by_id(incoming_id).unwrap();
+        assert!(matches!(resting_order.status, OrderStatus::Cancelled));
+        assert!(matches!(incoming_order.status, OrderStatus::Open));
+
+        let item_id = incoming_order.item_id;
+        let depth = order_book.depth(item_id, 5);
+        assert!(depth.asks.is_empty());
+        assert_eq!(depth.bids.len(), 1);
+    }
+
+    #[test]
+    fn should_cancel_incoming_order_on_self_trade() {
+        let (order_book, resting_id, incoming_id) =
+            self_trade_scenario(SelfTradePrevention::CancelIncoming);
+
+        assert!(order_book.trades.is_empty());
+        let resting_order = order_book.get_order_by_id(resting_id).unwrap();
+        let incoming_order = order_book.get_order_by_id(incoming_id).unwrap();
+        assert!(matches!(resting_order.status, OrderStatus::Open));
+        assert!(matches!(incoming_order.status, OrderStatus::Cancelled));
+
+        let depth = order_book.depth(resting_order.item_id, 5);
+        assert!(depth.bids.is_empty());
+        assert_eq!(depth.asks.len(), 1);
+    }
+
+    #[test]
+    fn should_cancel_both_orders_on_self_trade() {
+        let (order_book, resting_id, incoming_id) =
+            self_trade_scenario(SelfTradePrevention::CancelBoth);
+
+        assert!(order_book.trades.is_empty());
+        let resting_order = order_book.get_order_by_id(resting_id).unwrap();
+        let incoming_order = order_book.get_order_by_id(incoming_id).unwrap();
+        assert!(matches!(resting_order.status, OrderStatus::Cancelled));
+        assert!(matches!(incoming_order.status, OrderStatus::Cancelled));
+
+        let depth = order_book.depth(resting_order.item_id, 5);
+        assert!(depth.bids.is_empty());
+        assert!(depth.asks.is_empty());
+    }
+
+    #[test]
+    fn should_not_record_commands_unless_enabled() {
+        let (order_book, _, _) = self_trade_scenario(SelfTradePrevention::CancelBoth);
+
+        assert!(order_book.commands().is_empty());
+    }
+
+    #[test]
+    fn should_replay_commands_under_original_config() {
+        let config = OrderBookConfig {
+            record_commands: true,
+            self_trade_prevention: SelfTradePrevention::CancelBoth,
+            ..Default::default()
+        };
+        let mut order_book = OrderBookService::new_with_config(config.clone());
+        let item_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        for order_side in [OrderSide::Sell, OrderSide::Buy] {
+            order_book
+                .add_order(CreateOrderRequest {
+                    user_id,
+                    ..limit_order_request(item_id, order_side, "10.0", "50.0")
+                })
+                .unwrap();
+        }
+
+        let replayed_book =
+            OrderBookService::replay_commands(config, order_book.commands().to_vec());
+
+        assert!(replayed_book.trades.is_empty());
+        assert!(replayed_book.structurally_eq(&order_book));
+    }
+
+    #[test]
+    fn should_keep_prior_trades_when_fok_order_is_killed() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let other_item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(
+                other_item_id,
+                OrderSide::Sell,
+                "5.0",
+                "10.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                other_item_id,
+                OrderSide::Buy,
+                "5.0",
+                "10.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "20.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+        let prior_trade_ids: Vec<Uuid> = order_book.trades.iter().map(|trade| trade.id).collect();
+        assert_eq!(prior_trade_ids.len(), 2);
+
+        let resting_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "11.0",
+                "30.0",
+            ))
+            .unwrap();
+
+        let fok_order = order_book
+            .add_order(CreateOrderRequest {
+                time_in_force: TimeInForce::FOK,
+                ..limit_order_request(item_id, OrderSide::Buy, "11.0", "100.0")
+            })
+            .unwrap();
+
+        assert!(matches!(fok_order.status, OrderStatus::Cancelled));
+        assert_eq!(fok_order.quantity_filled, Decimal::ZERO);
+        let trade_ids: Vec<Uuid> = order_book.trades.iter().map(|trade| trade.id).collect();
+        assert_eq!(trade_ids, prior_trade_ids);
+
+        let fetched_resting_order = order_book.get_order_by_id(resting_order.id).unwrap();
+        assert_eq!(fetched_resting_order.quantity_filled, Decimal::ZERO);
+        assert_eq!(
+            order_book.depth(item_id, 5).asks,
+            vec![
+                (
+                    Decimal::from_str("10.0").unwrap(),
+                    Decimal::from_str("15.0").unwrap()
+                ),
+                (
+                    Decimal::from_str("11.0").unwrap(),
+                    Decimal::from_str("30.0").unwrap()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_return_killed_fok_order_instead_of_panicking() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let events: Arc<Mutex<Vec<OrderEvent>>> = Arc::new(Mutex::new(Vec::new()));
+        let listener_events = Arc::clone(&events);
+        order_book.set_event_listener(Box::new(move |event| {
+            listener_events.lock().unwrap().push(event.clone());
+        }));
+
+        let fok_order = order_book
+            .add_order(CreateOrderRequest {
+                time_in_force: TimeInForce::FOK,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "50.0")
+            })
+            .unwrap();
+
+        assert!(matches!(fok_order.status, OrderStatus::Cancelled));
+        assert_eq!(fok_order.quantity_filled, Decimal::ZERO);
+        assert!(order_book.get_order_by_id(fok_order.id).is_some());
+        assert_eq!(order_book.best_bid(item_id), None);
+        assert!(order_book.trades.is_empty());
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(&events[0], OrderEvent::Accepted(order) if order.id == fok_order.id));
+        assert!(matches!(
+            events[1],
+            OrderEvent::Cancelled { order_id } if order_id == fok_order.id
+        ));
+    }
+
+    #[test]
+    fn should_classify_each_submission_outcome() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let (result, resting_order) = order_book.add_order_with_result(limit_order_request(
+            item_id,
+            OrderSide::Sell,
+            "10.0",
+            "30.0",
+        ));
+        assert!(matches!(result, MatchResult::Resting));
+        assert!(resting_order.is_some());
+
+        let (result, _) = order_book.add_order_with_result(limit_order_request(
+            item_id,
+            OrderSide::Buy,
+            "10.0",
+            "10.0",
+        ));
+        assert!(matches!(result, MatchResult::FullyFilled));
+
+        let (result, partial_order) = order_book.add_order_with_result(limit_order_request(
+            item_id,
+            OrderSide::Buy,
+            "10.0",
+            "30.0",
+        ));
+        assert!(matches!(result, MatchResult::PartiallyFilledResting));
+        assert_eq!(
+            partial_order.unwrap().quantity_filled,
+            Decimal::from_str("20.0").unwrap()
+        );
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "11.0", "5.0"))
+            .unwrap();
+        let (result, killed_order) = order_book.add_order_with_result(CreateOrderRequest {
+            time_in_force: TimeInForce::FOK,
+            ..limit_order_request(item_id, OrderSide::Buy, "11.0", "50.0")
+        });
+        assert!(matches!(result, MatchResult::Cancelled));
+        assert_eq!(killed_order.unwrap().quantity_filled, Decimal::ZERO);
+
+        let (result, ioc_order) = order_book.add_order_with_result(CreateOrderRequest {
+            time_in_force: TimeInForce::IOC,
+            ..limit_order_request(item_id, OrderSide::Buy, "11.0", "8.0")
+        });
+        assert!(matches!(result, MatchResult::PartiallyFilledCancelled));
+        assert_eq!(
+            ioc_order.unwrap().quantity_filled,
+            Decimal::from_str("5.0").unwrap()
+        );
+
+        let (result, rejected_order) = order_book.add_order_with_result(limit_order_request(
+            item_id,
+            OrderSide::Buy,
+            "10.0",
+            "0.0",
+        ));
+        assert!(matches!(
+            result,
+            MatchResult::Rejected(OrderError::NonPositiveQuantity)
+        ));
+        assert!(rejected_order.is_none());
+    }
+
+    #[test]
+    fn should_kill_fok_order_that_cannot_fill_across_levels() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let first_level = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "50.0",
+            ))
+            .unwrap();
+        let second_level = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "11.0",
+                "50.0",
+            ))
+            .unwrap();
+        let depth_before = order_book.depth(item_id, 5);
+
+        for order_type in [OrderType::Limit, OrderType::Market] {
+            let fok_order = order_book
+                .add_order(CreateOrderRequest {
+                    order_type,
+                    time_in_force: TimeInForce::FOK,
+                    ..limit_order_request(item_id, OrderSide::Buy, "11.0", "150.0")
+                })
+                .unwrap();
+
+            assert!(matches!(fok_order.status, OrderStatus::Cancelled));
+            assert_eq!(fok_order.quantity_filled, Decimal::ZERO);
+        }
+
+        assert!(order_book.trades.is_empty());
+        assert_eq!(order_book.depth(item_id, 5).asks, depth_before.asks);
+        for resting_order_id in [first_level.id, second_level.id] {
+            let resting_order = order_book.get_order_by_id(resting_order_id).unwrap();
+            assert!(matches!(resting_order.status, OrderStatus::Open));
+            assert_eq!(resting_order.quantity_filled, Decimal::ZERO);
+        }
+    }
+
+    #[test]
+    fn should_skip_expired_own_order_when_checking_fok_liquidity() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            self_trade_prevention: SelfTradePrevention::CancelIncoming,
+            ..Default::default()
+        });
+        let clock = MockClock::new(Utc::now());
+        order_book.set_clock(Arc::new(clock.clone()));
+        let item_id = Uuid::new_v4();
+        let trader = Uuid::new_v4();
+
+        order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                time_in_force: TimeInForce::GTD(clock.now() + Duration::hours(1)),
+                ..limit_order_request(item_id, OrderSide::Sell, "10.0", "50.0")
+            })
+            .unwrap();
+        clock.advance(Duration::hours(2));
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "11.0",
+                "100.0",
+            ))
+            .unwrap();
+
+        let fok_order = order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                time_in_force: TimeInForce::FOK,
+                ..limit_order_request(item_id, OrderSide::Buy, "11.0", "100.0")
+            })
+            .unwrap();
+
+        assert!(matches!(fok_order.status, OrderStatus::Closed));
+        assert_eq!(order_book.trades.len(), 1);
+        assert_eq!(
+            order_book.trades[0].price,
+            Decimal::from_str("11.0").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_fill_fok_order_when_liquidity_suffices() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "60.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "11.0",
+                "60.0",
+            ))
+            .unwrap();
+
+        let fok_order = order_book
+            .add_order(CreateOrderRequest {
+                time_in_force: TimeInForce::FOK,
+                ..limit_order_request(item_id, OrderSide::Buy, "11.0", "100.0")
+            })
+            .unwrap();
+
+        assert!(matches!(fok_order.status, OrderStatus::Closed));
+        assert_eq!(
+            fok_order.quantity_filled,
+            Decimal::from_str("100.0").unwrap()
+        );
+        assert_eq!(order_book.trades.len(), 2);
+    }
+
+    #[test]
+    fn should_execute_order_only_when_min_quantity_is_available() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let min_fill_request = CreateOrderRequest {
+            min_quantity: Some(Decimal::from_str("60.0").unwrap()),
+            ..limit_order_request(item_id, OrderSide::Buy, "10.0", "100.0")
+        };
+
+        let thin_ask = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "40.0",
+            ))
+            .unwrap();
+        let killed_order = order_book.add_order(min_fill_request.clone()).unwrap();
+
+        assert!(matches!(killed_order.status, OrderStatus::Cancelled));
+        assert_eq!(killed_order.quantity_filled, Decimal::ZERO);
+        assert!(order_book.trades.is_empty());
+        assert_eq!(order_book.best_bid(item_id), None);
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "30.0",
+            ))
+            .unwrap();
+        let filled_order = order_book.add_order(min_fill_request).unwrap();
+
+        assert!(matches!(filled_order.status, OrderStatus::PartiallyFilled));
+        assert_eq!(
+            filled_order.quantity_filled,
+            Decimal::from_str("70.0").unwrap()
+        );
+        assert_eq!(order_book.trades[0].sell_order_id, thin_ask.id);
+        assert_eq!(
+            order_book.best_level(item_id, OrderSide::Buy),
+            Some((
+                Decimal::from_str("10.0").unwrap(),
+                Decimal::from_str("30.0").unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn should_reject_min_quantity_outside_order_quantity() {
+        let mut order_book = OrderBookService::new();
+
+        for min_quantity in ["0.0", "150.0"] {
+            let result = order_book.add_order(CreateOrderRequest {
+                min_quantity: Some(Decimal::from_str(min_quantity).unwrap()),
+                ..limit_order_request(Uuid::new_v4(), OrderSide::Buy, "10.0", "100.0")
+            });
+            assert!(matches!(result, Err(OrderError::InvalidMinQuantity)));
+        }
+    }
+
+    #[test]
+    fn should_expire_day_order_after_expiry() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let day_order = order_book
+            .add_order(CreateOrderRequest {
+                time_in_force: TimeInForce::DAY,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "50.0")
+            })
+            .unwrap();
+        let gtc_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "50.0"))
+            .unwrap();
+
+        let expired_order_ids = order_book.expire_orders(Utc::now() + Duration::days(2));
+
+        assert_eq!(expired_order_ids, vec![day_order.id]);
+        let expired_order = order_book.get_order_by_id(day_order.id).unwrap();
+        assert!(matches!(expired_order.status, OrderStatus::Cancelled));
+        let remaining_order = order_book.get_order_by_id(gtc_order.id).unwrap();
+        assert!(matches!(remaining_order.status, OrderStatus::Open));
+        assert_eq!(
+            order_book.depth(item_id, 5).bids,
+            vec![(
+                Decimal::from_str("9.0").unwrap(),
+                Decimal::from_str("50.0").unwrap()
+            )]
+        );
+    }
+
+    #[test]
+    fn should_expire_only_the_given_item() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let other_item_id = Uuid::new_v4();
+
+        let day_order = order_book
+            .add_order(CreateOrderRequest {
+                time_in_force: TimeInForce::DAY,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "50.0")
+            })
+            .unwrap();
+        let other_day_order = order_book
+            .add_order(CreateOrderRequest {
+                time_in_force: TimeInForce::DAY,
+                ..limit_order_request(other_item_id, OrderSide::Buy, "10.0", "50.0")
+            })
+            .unwrap();
+
+        let expired_order_ids =
+            order_book.expire_orders_for_item(item_id, Utc::now() + Duration::days(2));
+
+        assert_eq!(expired_order_ids, vec![day_order.id]);
+        let other_order = order_book.get_order_by_id(other_day_order.id).unwrap();
+        assert!(matches!(other_order.status, OrderStatus::Open));
+        assert_eq!(order_book.best_bid(item_id), None);
+        assert_eq!(
+            order_book.best_bid(other_item_id),
+            Some(Decimal::from_str("10.0").unwrap())
+        );
+    }
+
+    #[test]
+    fn should_not_expire_unexpired_day_order() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let day_order = order_book
+            .add_order(CreateOrderRequest {
+                time_in_force: TimeInForce::DAY,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "50.0")
+            })
+            .unwrap();
+
+        let expired_order_ids = order_book.expire_orders(Utc::now());
+
+        assert!(expired_order_ids.is_empty());
+        let fetched_order = order_book.get_order_by_id(day_order.id).unwrap();
+        assert!(matches!(fetched_order.status, OrderStatus::Open));
+        assert_eq!(order_book.depth(item_id, 5).bids.len(), 1);
+    }
+
+    #[test]
+    fn should_cancel_unfilled_ioc_order_instead_of_resting() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let ioc_order = order_book
+            .add_order(CreateOrderRequest {
+                time_in_force: TimeInForce::IOC,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "50.0")
+            })
+            .unwrap();
+
+        assert!(matches!(ioc_order.status, OrderStatus::Cancelled));
+        assert!(order_book.depth(item_id, 5).bids.is_empty());
+    }
+
+    #[test]
+    fn should_restore_identical_book_from_snapshot() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "50.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "30.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "11.0",
+                "40.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "25.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "20.0"))
+            .unwrap();
+
+        let snapshot = order_book.snapshot();
+        let mut restored_order_book = OrderBookService::restore(snapshot);
+
+        let depth = order_book.depth(item_id, 10);
+        let restored_depth = restored_order_book.depth(item_id, 10);
+        assert_eq!(restored_depth.bids, depth.bids);
+        assert_eq!(restored_depth.asks, depth.asks);
+        assert_eq!(restored_order_book.get_orders().len(), 5);
+        assert_eq!(restored_order_book.trades.len(), order_book.trades.len());
+        for (restored_trade, trade) in restored_order_book.trades.iter().zip(&order_book.trades) {
+            assert_eq!(restored_trade.id, trade.id);
+            assert_eq!(restored_trade.quantity, trade.quantity);
+            assert_eq!(restored_trade.price, trade.price);
+        }
+
+        let partially_filled_id = order_book.trades[0].sell_order_id;
+        let buy_order = restored_order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "10.0"))
+            .unwrap();
+        assert!(matches!(buy_order.status, OrderStatus::Closed));
+        assert_eq!(
+            restored_order_book.trades.last().unwrap().sell_order_id,
+            partially_filled_id
+        );
+    }
+
+    #[test]
+    fn should_return_only_trades_from_this_submission() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "50.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "20.0"))
+            .unwrap();
+        assert_eq!(order_book.trades.len(), 1);
+
+        let outcome = order_book
+            .add_order_with_trades(limit_order_request(item_id, OrderSide::Buy, "10.0", "10.0"))
+            .unwrap();
+
+        assert!(matches!(outcome.order.status, OrderStatus::Closed));
+        assert_eq!(outcome.trades.len(), 1);
+        assert_eq!(outcome.trades[0].buy_order_id, outcome.order.id);
+        assert_eq!(
+            outcome.trades[0].quantity,
+            Decimal::from_str("10.0").unwrap()
+        );
+        assert_eq!(order_book.trades.len(), 2);
+
+        let resting_outcome = order_book
+            .add_order_with_trades(limit_order_request(item_id, OrderSide::Buy, "9.0", "10.0"))
+            .unwrap();
+        assert!(resting_outcome.trades.is_empty());
+    }
+
+    #[test]
+    fn should_emit_events_in_order() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let events: Arc<Mutex<Vec<OrderEvent>>> = Arc::new(Mutex::new(Vec::new()));
+        let listener_events = Arc::clone(&events);
+        order_book.set_event_listener(Box::new(move |event| {
+            listener_events.lock().unwrap().push(event.clone());
+        }));
+
+        let sell_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "50.0",
+            ))
+            .unwrap();
+        let buy_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "50.0"))
+            .unwrap();
+        let _ = order_book.add_order(limit_order_request(item_id, OrderSide::Buy, "-1.0", "5.0"));
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 6);
+        assert!(matches!(&events[0], OrderEvent::Accepted(order) if order.id == sell_order.id));
+        assert!(matches!(&events[1], OrderEvent::Accepted(order) if order.id == buy_order.id));
+        assert!(matches!(
+            &events[2],
+            OrderEvent::Traded(trade)
+                if trade.buy_order_id == buy_order.id && trade.sell_order_id == sell_order.id
+        ));
+        assert!(matches!(
+            &events[3],
+            OrderEvent::StatusChanged { order_id, status: OrderStatus::Closed }
+                if *order_id == sell_order.id
+        ));
+        assert!(matches!(
+            &events[4],
+            OrderEvent::StatusChanged { order_id, status: OrderStatus::Closed }
+                if *order_id == buy_order.id
+        ));
+        assert!(matches!(
+            &events[5],
+            OrderEvent::Rejected {
+                reason: OrderError::NegativePrice
+            }
+        ));
+    }
+
+    #[test]
+    fn should_resolve_buyer_and_seller_for_each_trade() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let buyer = Uuid::new_v4();
+        let first_seller = Uuid::new_v4();
+        let second_seller = Uuid::new_v4();
+        let settlements: Arc<Mutex<Vec<(Uuid, Uuid, Uuid)>>> = Arc::new(Mutex::new(Vec::new()));
+        let listener_settlements = Arc::clone(&settlements);
+        order_book.on_trade(move |trade, buy_order, sell_order| {
+            listener_settlements.lock().unwrap().push((
+                trade.id,
+                buy_order.user_id,
+                sell_order.user_id,
+            ));
+        });
+
+        for (seller, price) in [(first_seller, "10.0"), (second_seller, "10.5")] {
+            order_book
+                .add_order(CreateOrderRequest {
+                    user_id: seller,
+                    ..limit_order_request(item_id, OrderSide::Sell, price, "5.0")
+                })
+                .unwrap();
+        }
+        order_book
+            .add_order(CreateOrderRequest {
+                user_id: buyer,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.5", "10.0")
+            })
+            .unwrap();
+
+        assert_eq!(
+            *settlements.lock().unwrap(),
+            vec![
+                (order_book.trades[0].id, buyer, first_seller),
+                (order_book.trades[1].id, buyer, second_seller),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_emit_cancelled_and_expired_events() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let events: Arc<Mutex<Vec<OrderEvent>>> = Arc::new(Mutex::new(Vec::new()));
+        let listener_events = Arc::clone(&events);
+        order_book.set_event_listener(Box::new(move |event| {
+            listener_events.lock().unwrap().push(event.clone());
+        }));
+
+        let cancelled_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "50.0"))
+            .unwrap();
+        let day_order = order_book
+            .add_order(CreateOrderRequest {
+                time_in_force: TimeInForce::DAY,
+                ..limit_order_request(item_id, OrderSide::Buy, "9.0", "50.0")
+            })
+            .unwrap();
+        order_book.cancel_order(cancelled_order.id);
+        order_book.expire_orders(Utc::now() + Duration::days(2));
+
+        let events = events.lock().unwrap();
+        assert!(matches!(
+            &events[2],
+            OrderEvent::Cancelled { order_id } if *order_id == cancelled_order.id
+        ));
+        assert!(matches!(
+            &events[3],
+            OrderEvent::Expired { order_id } if *order_id == day_order.id
+        ));
+    }
+
+    #[test]
+    fn should_not_rest_unfilled_market_order_remainder() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "40.0",
+            ))
+            .unwrap();
+
+        let market_order = order_book
+            .add_order(CreateOrderRequest {
+                order_type: OrderType::Market,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "100.0")
+            })
+            .unwrap();
+
+        assert!(matches!(market_order.status, OrderStatus::Cancelled));
+        assert_eq!(
+            market_order.quantity_filled,
+            Decimal::from_str("40.0").unwrap()
+        );
+        assert!(order_book.depth(item_id, 5).bids.is_empty());
+        assert!(order_book.depth(item_id, 5).asks.is_empty());
+    }
+
+    #[test]
+    fn should_get_trades_for_item() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let other_item_id = Uuid::new_v4();
+
+        for trade_item_id in [item_id, other_item_id, item_id] {
+            order_book
+                .add_order(limit_order_request(
+                    trade_item_id,
+                    OrderSide::Sell,
+                    "10.0",
+                    "5.0",
+                ))
+                .unwrap();
+            order_book
+                .add_order(limit_order_request(
+                    trade_item_id,
+                    OrderSide::Buy,
+                    "10.0",
+                    "5.0",
+                ))
+                .unwrap();
+        }
+
+        let item_trades = order_book.trades_for_item(item_id);
+        assert_eq!(item_trades.len(), 2);
+        assert!(item_trades.iter().all(|trade| trade.item_id == item_id));
+        assert_eq!(order_book.trades_for_item(other_item_id).len(), 1);
+        assert!(order_book.trades_for_item(Uuid::new_v4()).is_empty());
+    }
+
+    #[test]
+    fn should_get_trades_for_user_on_both_sides() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+
+        let user_buy = order_book
+            .add_order(CreateOrderRequest {
+                user_id,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0")
+            })
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+
+        let user_sell = order_book
+            .add_order(CreateOrderRequest {
+                user_id,
+                ..limit_order_request(item_id, OrderSide::Sell, "11.0", "5.0")
+            })
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "11.0", "5.0"))
+            .unwrap();
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "12.0", "5.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "12.0", "5.0"))
+            .unwrap();
+
+        let user_trades = order_book.trades_for_user(user_id);
+        assert_eq!(order_book.trades.len(), 3);
+        assert_eq!(user_trades.len(), 2);
+        assert_eq!(user_trades[0].buy_order_id, user_buy.id);
+        assert_eq!(user_trades[1].sell_order_id, user_sell.id);
+    }
+
+    #[test]
+    fn should_match_after_repricing_resting_order_across_spread() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let buy_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "50.0"))
+            .unwrap();
+        let sell_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "30.0",
+            ))
+            .unwrap();
+        assert!(order_book.trades.is_empty());
+
+        let repriced_order = order_book
+            .update_order_price(buy_order.id, Decimal::from_str("10.0").unwrap())
+            .unwrap();
+
+        assert!(matches!(
+            repriced_order.status,
+            OrderStatus::PartiallyFilled
+        ));
+        assert_eq!(order_book.trades.len(), 1);
+        assert_eq!(order_book.trades[0].buy_order_id, buy_order.id);
+        assert_eq!(order_book.trades[0].sell_order_id, sell_order.id);
+        assert_eq!(
+            order_book.trades[0].price,
+            Decimal::from_str("10.0").unwrap()
+        );
+
+        let depth = order_book.depth(item_id, 5);
+        assert_eq!(
+            depth.bids,
+            vec![(
+                Decimal::from_str("10.0").unwrap(),
+                Decimal::from_str("20.0").unwrap()
+            )]
+        );
+        assert!(depth.asks.is_empty());
+    }
+
+    #[test]
+    fn should_never_match_an_order_against_itself() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let better_sell_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "9.0", "5.0"))
+            .unwrap();
+        let queued_ahead = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "30.0",
+            ))
+            .unwrap();
+        let sell_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "30.0",
+            ))
+            .unwrap();
+        let mut mirrored_order = sell_order.clone();
+        mirrored_order.order_side = OrderSide::Buy;
+        order_book.execute_order_matching(&mut mirrored_order);
+
+        assert!(order_book.trades.is_empty());
+        assert!(matches!(
+            order_book.get_order_by_id(sell_order.id).unwrap().status,
+            OrderStatus::Open
+        ));
+        let queue: Vec<Uuid> = order_book
+            .resting_orders(item_id, OrderSide::Sell)
+            .map(|order| order.id)
+            .collect();
+        assert_eq!(
+            queue,
+            vec![better_sell_order.id, queued_ahead.id, sell_order.id]
+        );
+        assert!(order_book.level2(item_id, OrderSide::Buy, 5).is_empty());
+        order_book.cancel_order(better_sell_order.id);
+        order_book.cancel_order(queued_ahead.id);
+
+        for price in ["9.0", "10.0", "11.0"] {
+            order_book.update_order_price(sell_order.id, Decimal::from_str(price).unwrap());
+        }
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "11.0", "5.0"))
+            .unwrap();
+
+        assert_eq!(order_book.trades.len(), 1);
+        assert!(
+            order_book
+                .trades
+                .iter()
+                .all(|trade| trade.buy_order_id != trade.sell_order_id)
+        );
+        assert_eq!(order_book.level2(item_id, OrderSide::Sell, 5).len(), 1);
+    }
+
+    #[test]
+    fn should_move_repriced_order_to_new_price_level() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let buy_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "50.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "8.0", "10.0"))
+            .unwrap();
+
+        order_book.update_order_price(buy_order.id, Decimal::from_str("8.0").unwrap());
+
+        assert_eq!(
+            order_book.depth(item_id, 5).bids,
+            vec![(
+                Decimal::from_str("8.0").unwrap(),
+                Decimal::from_str("60.0").unwrap()
+            )]
+        );
+    }
+
+    #[test]
+    fn should_compute_vwap_over_trades() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        assert!(order_book.vwap(item_id, None).is_none());
+
+        for (price, quantity) in [("10.0", "10.0"), ("12.0", "30.0"), ("14.0", "10.0")] {
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    OrderSide::Sell,
+                    price,
+                    quantity,
+                ))
+                .unwrap();
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    OrderSide::Buy,
+                    price,
+                    quantity,
+                ))
+                .unwrap();
+        }
+
+        assert_eq!(
+            order_book.vwap(item_id, None),
+            Some(Decimal::from_str("12.0").unwrap())
+        );
+        assert_eq!(
+            order_book.vwap(item_id, Some(Utc::now() - Duration::hours(1))),
+            Some(Decimal::from_str("12.0").unwrap())
+        );
+        assert!(
+            order_book
+                .vwap(item_id, Some(Utc::now() + Duration::hours(1)))
+                .is_none()
+        );
+        assert!(order_book.vwap(Uuid::new_v4(), None).is_none());
+    }
+
+    #[test]
+    fn should_get_best_bid_best_ask_and_spread() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        for (side, price) in [
+            (OrderSide::Buy, "9.0"),
+            (OrderSide::Buy, "9.5"),
+            (OrderSide::Sell, "10.5"),
+            (OrderSide::Sell, "11.0"),
+        ] {
+            order_book
+                .add_order(limit_order_request(item_id, side, price, "10.0"))
+                .unwrap();
+        }
+
+        assert_eq!(
+            order_book.best_bid(item_id),
+            Some(Decimal::from_str("9.5").unwrap())
+        );
+        assert_eq!(
+            order_book.best_ask(item_id),
+            Some(Decimal::from_str("10.5").unwrap())
+        );
+        assert_eq!(
+            order_book.spread(item_id),
+            Some(Decimal::from_str("1.0").unwrap())
+        );
+    }
+
+    #[test]
+    fn should_compute_spread_in_basis_points_of_mid() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        for (side, price) in [(OrderSide::Buy, "99.99"), (OrderSide::Sell, "100.02")] {
+            order_book
+                .add_order(limit_order_request(item_id, side, price, "10.0"))
+                .unwrap();
+        }
+
+        assert_eq!(
+            order_book.spread_bps(item_id),
+            Some(Decimal::from_str("2.9999").unwrap())
+        );
+        assert_eq!(order_book.spread_bps(Uuid::new_v4()), None);
+    }
+
+    #[test]
+    fn should_return_no_spread_bps_when_mid_is_zero() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            allow_negative_prices: true,
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+
+        for (side, price) in [(OrderSide::Buy, "-1.0"), (OrderSide::Sell, "1.0")] {
+            order_book
+                .add_order(limit_order_request(item_id, side, price, "10.0"))
+                .unwrap();
+        }
+
+        assert_eq!(order_book.mid_price(item_id), Some(Decimal::ZERO));
+        assert_eq!(order_book.spread_bps(item_id), None);
+    }
+
+    #[test]
+    fn should_return_none_for_empty_book_side() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "10.0"))
+            .unwrap();
+
+        assert_eq!(
+            order_book.best_bid(item_id),
+            Some(Decimal::from_str("9.0").unwrap())
+        );
+        assert!(order_book.best_ask(item_id).is_none());
+        assert!(order_book.spread(item_id).is_none());
+        assert!(order_book.best_bid(Uuid::new_v4()).is_none());
+    }
+
+    #[test]
+    fn should_reject_post_only_order_that_would_cross() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "50.0",
+            ))
+            .unwrap();
+
+        let result = order_book.add_order(CreateOrderRequest {
+            post_only: true,
+            ..limit_order_request(item_id, OrderSide::Buy, "10.0", "20.0")
+        });
+
+        assert!(matches!(result, Err(OrderError::PostOnlyWouldCross)));
+        assert!(order_book.trades.is_empty());
+        assert_eq!(order_book.get_orders().len(), 1);
+    }
+
+    #[test]
+    fn should_report_whether_reprice_would_cross() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "11.0", "5.0"))
+            .unwrap();
+        let buy_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+
+        assert!(order_book.would_cross(buy_order.id, Decimal::from_str("11.0").unwrap()));
+        assert!(!order_book.would_cross(buy_order.id, Decimal::from_str("10.5").unwrap()));
+        assert!(!order_book.would_cross(Uuid::new_v4(), Decimal::from_str("11.0").unwrap()));
+        assert_eq!(
+            order_book.get_order_by_id(buy_order.id).unwrap().price,
+            Decimal::from_str("10.0").unwrap()
+        );
+        assert!(order_book.trades.is_empty());
+
+        order_book.cancel_order(buy_order.id);
+        assert!(!order_book.would_cross(buy_order.id, Decimal::from_str("11.0").unwrap()));
+    }
+
+    #[test]
+    fn should_rest_post_only_order_that_does_not_cross() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "50.0",
+            ))
+            .unwrap();
+
+        let post_only_order = order_book
+            .add_order(CreateOrderRequest {
+                post_only: true,
+                ..limit_order_request(item_id, OrderSide::Buy, "9.9", "20.0")
+            })
+            .unwrap();
+
+        assert!(matches!(post_only_order.status, OrderStatus::Open));
+        assert!(order_book.trades.is_empty());
+        assert_eq!(
+            order_book.best_bid(item_id),
+            Some(Decimal::from_str("9.9").unwrap())
+        );
+    }
+
+    #[test]
+    fn should_charge_maker_and_taker_fees() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            fee_schedule: FeeSchedule {
+                maker_bps: Decimal::from_str("10").unwrap(),
+                taker_bps: Decimal::from_str("25").unwrap(),
+            },
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "20.0",
+                "50.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "20.0", "50.0"))
+            .unwrap();
+
+        let trade = &order_book.trades[0];
+        assert_eq!(trade.maker_fee, Decimal::from_str("1.0").unwrap());
+        assert_eq!(trade.taker_fee, Decimal::from_str("2.5").unwrap());
+    }
+
+    #[test]
+    fn should_charge_no_fees_by_default() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "20.0",
+                "50.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "20.0", "50.0"))
+            .unwrap();
+
+        assert_eq!(order_book.trades[0].maker_fee, Decimal::ZERO);
+        assert_eq!(order_book.trades[0].taker_fee, Decimal::ZERO);
+    }
+
+    #[test]
+    fn should_reject_non_positive_quantity_update() {
+        let mut order_book = OrderBookService::new();
+        let order = order_book
+            .add_order(limit_order_request(
+                Uuid::new_v4(),
+                OrderSide::Buy,
+                "20.0",
+                "50.0",
+            ))
+            .unwrap();
+
+        let zero = order_book.update_order_quantity(order.id, Decimal::ZERO);
+        assert!(matches!(zero, Err(OrderError::NonPositiveQuantity)));
+
+        let negative =
+            order_book.update_order_quantity(order.id, Decimal::from_str("-5.0").unwrap());
+        assert!(matches!(negative, Err(OrderError::NonPositiveQuantity)));
+
+        let order = order_book.get_order_by_id(order.id).unwrap();
+        assert_eq!(order.quantity, Decimal::from_str("50.0").unwrap());
+    }
+
+    #[test]
+    fn should_reject_quantity_update_below_quantity_filled() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let sell_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "20.0",
+                "50.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "20.0", "30.0"))
+            .unwrap();
+
+        let result =
+            order_book.update_order_quantity(sell_order.id, Decimal::from_str("20.0").unwrap());
+        assert!(matches!(result, Err(OrderError::QuantityBelowFilled)));
+    }
+
+    #[test]
+    fn should_reject_quantity_update_for_unknown_order() {
+        let mut order_book = OrderBookService::new();
+        let result =
+            order_book.update_order_quantity(Uuid::new_v4(), Decimal::from_str("10.0").unwrap());
+        assert!(matches!(result, Err(OrderError::OrderNotFound)));
+    }
+
+    #[test]
+    fn should_reject_quantity_update_for_cancelled_order() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let sell_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "20.0",
+                "50.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "20.0", "30.0"))
+            .unwrap();
+        order_book.cancel_order(sell_order.id);
+
+        let result =
+            order_book.update_order_quantity(sell_order.id, Decimal::from_str("30.0").unwrap());
+
+        assert!(matches!(result, Err(OrderError::OrderNotActive)));
+        let cancelled_order = order_book.get_order_by_id(sell_order.id).unwrap();
+        assert!(matches!(cancelled_order.status, OrderStatus::Cancelled));
+        assert_eq!(cancelled_order.quantity, Decimal::from_str("50.0").unwrap());
+    }
+
+    #[test]
+    fn should_close_order_when_quantity_reduced_to_quantity_filled() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let sell_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "20.0",
+                "50.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "20.0", "30.0"))
+            .unwrap();
+
+        let updated_order = order_book
+            .update_order_quantity(sell_order.id, Decimal::from_str("30.0").unwrap())
+            .unwrap();
+        assert!(matches!(updated_order.status, OrderStatus::Closed));
+        assert_eq!(order_book.best_ask(item_id), None);
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "20.0", "10.0"))
+            .unwrap();
+        assert_eq!(order_book.trades.len(), 1);
+    }
+
+    #[test]
+    fn should_reject_invalid_requests_with_typed_errors() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let negative_price =
+            order_book.add_order(limit_order_request(item_id, OrderSide::Buy, "-1.0", "10.0"));
+        assert!(matches!(negative_price, Err(OrderError::NegativePrice)));
+        assert_eq!(
+            negative_price.unwrap_err().to_string(),
+            "Price cannot be negative"
+        );
+
+        let zero_quantity =
+            order_book.add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "0.0"));
+        assert!(matches!(
+            zero_quantity,
+            Err(OrderError::NonPositiveQuantity)
+        ));
+
+        let negative_trigger = order_book.add_order(CreateOrderRequest {
+            order_type: OrderType::Stop {
+                trigger: Decimal::from_str("-5.0").unwrap(),
+            },
+            ..limit_order_request(item_id, OrderSide::Buy, "10.0", "10.0")
+        });
+        assert!(matches!(
+            negative_trigger,
+            Err(OrderError::NegativeTriggerPrice)
+        ));
+    }
+
+    #[test]
+    fn should_reject_price_not_aligned_to_tick_size() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book.set_tick_size(item_id, Decimal::from_str("0.01").unwrap());
+
+        let result = order_book.add_order(limit_order_request(
+            item_id,
+            OrderSide::Buy,
+            "10.005",
+            "10.0",
+        ));
+        assert!(matches!(
+            result,
+            Err(OrderError::PriceNotTickAligned { price, tick_size })
+                if price == Decimal::from_str("10.005").unwrap()
+                    && tick_size == Decimal::from_str("0.01").unwrap()
+        ));
+
+        let stop_limit = order_book.add_order(CreateOrderRequest {
+            order_type: OrderType::StopLimit {
+                trigger: Decimal::from_str("10.00").unwrap(),
+                limit: Decimal::from_str("10.015").unwrap(),
+            },
+            ..limit_order_request(item_id, OrderSide::Buy, "10.00", "10.0")
+        });
+        assert!(matches!(
+            stop_limit,
+            Err(OrderError::PriceNotTickAligned { .. })
+        ));
+        assert!(order_book.get_orders().is_empty());
+    }
+
+    #[test]
+    fn should_accept_tick_aligned_price() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book.set_tick_size(item_id, Decimal::from_str("0.01").unwrap());
+
+        let result = order_book.add_order(limit_order_request(
+            item_id,
+            OrderSide::Buy,
+            "10.00",
+            "10.0",
+        ));
+        assert!(result.is_ok());
+
+        let other_item = order_book.add_order(limit_order_request(
+            Uuid::new_v4(),
+            OrderSide::Buy,
+            "10.005",
+            "10.0",
+        ));
+        assert!(other_item.is_ok());
+    }
+
+    #[test]
+    fn should_reject_quantity_below_minimum_size() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book.set_size_limits(item_id, Some(Decimal::from_str("5.0").unwrap()), None);
+
+        let result =
+            order_book.add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "4.0"));
+        assert!(matches!(
+            result,
+            Err(OrderError::QuantityBelowMinimum { quantity, minimum })
+                if quantity == Decimal::from_str("4.0").unwrap()
+                    && minimum == Decimal::from_str("5.0").unwrap()
+        ));
+    }
+
+    #[test]
+    fn should_reject_quantity_above_maximum_size() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book.set_size_limits(item_id, None, Some(Decimal::from_str("100.0").unwrap()));
+
+        let result = order_book.add_order(limit_order_request(
+            item_id,
+            OrderSide::Sell,
+            "10.0",
+            "150.0",
+        ));
+        assert!(matches!(
+            result,
+            Err(OrderError::QuantityAboveMaximum { quantity, maximum })
+                if quantity == Decimal::from_str("150.0").unwrap()
+                    && maximum == Decimal::from_str("100.0").unwrap()
+        ));
+        assert!(order_book.get_orders().is_empty());
+    }
+
+    #[test]
+    fn should_accept_quantity_within_size_limits() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book.set_size_limits(
+            item_id,
+            Some(Decimal::from_str("5.0").unwrap()),
+            Some(Decimal::from_str("100.0").unwrap()),
+        );
+
+        for quantity in ["5.0", "50.0", "100.0"] {
+            let result = order_book.add_order(limit_order_request(
+                item_id,
+                OrderSide::Buy,
+                "10.0",
+                quantity,
+            ));
+            assert!(result.is_ok());
+        }
+    }
+
+    #[test]
+    fn should_iterate_resting_orders_in_matching_priority() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let first_at_ten = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+        let at_eleven = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "11.0", "5.0"))
+            .unwrap();
+        let second_at_ten = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+        let bid_low = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "8.0", "5.0"))
+            .unwrap();
+        let bid_high = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "5.0"))
+            .unwrap();
+
+        let asks: Vec<Uuid> = order_book
+            .resting_orders(item_id, OrderSide::Sell)
+            .map(|order| order.id)
+            .collect();
+        assert_eq!(asks, vec![first_at_ten.id, second_at_ten.id, at_eleven.id]);
+
+        let bids: Vec<Uuid> = order_book
+            .resting_orders(item_id, OrderSide::Buy)
+            .map(|order| order.id)
+            .collect();
+        assert_eq!(bids, vec![bid_high.id, bid_low.id]);
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "1.0"))
+            .unwrap();
+        assert_eq!(order_book.trades[0].sell_order_id, asks[0]);
+        assert_eq!(
+            order_book
+                .resting_orders(Uuid::new_v4(), OrderSide::Sell)
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn should_return_level2_orders_up_to_depth_in_queue_order() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let first_at_ten = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "5.0"))
+            .unwrap();
+        let second_at_ten = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "7.0"))
+            .unwrap();
+        let at_eleven = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "11.0", "5.0"))
+            .unwrap();
+
+        let level2 = order_book.level2(item_id, OrderSide::Buy, 3);
+
+        let level2_ids: Vec<Uuid> = level2.iter().map(|order| order.id).collect();
+        assert_eq!(
+            level2_ids,
+            vec![at_eleven.id, first_at_ten.id, second_at_ten.id]
+        );
+        assert_eq!(level2[2].quantity, Decimal::from_str("7.0").unwrap());
+        assert_eq!(order_book.level2(item_id, OrderSide::Buy, 10).len(), 4);
+        assert!(order_book.level2(item_id, OrderSide::Sell, 3).is_empty());
+    }
+
+    #[test]
+    fn should_replace_order_with_fresh_id_and_time_priority() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let original = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+        let other = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+
+        let replacement = order_book
+            .replace_order(
+                original.id,
+                limit_order_request(item_id, OrderSide::Sell, "10.0", "8.0"),
+            )
+            .unwrap();
+
+        assert_ne!(replacement.id, original.id);
+        assert!(matches!(
+            order_book.get_order_by_id(original.id).unwrap().status,
+            OrderStatus::Cancelled
+        ));
+
+        let asks: Vec<Uuid> = order_book
+            .resting_orders(item_id, OrderSide::Sell)
+            .map(|order| order.id)
+            .collect();
+        assert_eq!(asks, vec![other.id, replacement.id]);
+    }
+
+    #[test]
+    fn should_keep_original_order_when_replacement_is_invalid() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let original = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+
+        let result = order_book.replace_order(
+            original.id,
+            limit_order_request(item_id, OrderSide::Buy, "10.0", "0.0"),
+        );
+
+        assert!(matches!(result, Err(OrderError::NonPositiveQuantity)));
+        assert!(matches!(
+            order_book.get_order_by_id(original.id).unwrap().status,
+            OrderStatus::Open
+        ));
+        assert_eq!(
+            order_book.best_bid(item_id),
+            Some(Decimal::from_str("10.0").unwrap())
+        );
+
+        let unknown = order_book.replace_order(
+            Uuid::new_v4(),
+            limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"),
+        );
+        assert!(matches!(unknown, Err(OrderError::OrderNotFound)));
+    }
+
+    #[test]
+    fn should_order_same_instant_orders_by_sequence() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let sell_orders: Vec<_> = (0..5)
+            .map(|_| {
+                order_book
+                    .add_order(CreateOrderRequest {
+                        time_in_force: TimeInForce::DAY,
+                        ..limit_order_request(item_id, OrderSide::Sell, "10.0", "1.0")
+                    })
+                    .unwrap()
+            })
+            .collect();
+
+        for pair in sell_orders.windows(2) {
+            assert!(pair[0].seq < pair[1].seq);
+        }
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "2.0"))
+            .unwrap();
+        assert_eq!(order_book.trades[0].sell_order_id, sell_orders[0].id);
+        assert_eq!(order_book.trades[1].sell_order_id, sell_orders[1].id);
+        assert!(order_book.trades[0].seq < order_book.trades[1].seq);
+
+        let expired = order_book.expire_orders(Utc::now() + Duration::days(2));
+        let expected: Vec<Uuid> = sell_orders[2..].iter().map(|order| order.id).collect();
+        assert_eq!(expired, expected);
+    }
+
+    #[test]
+    fn should_cancel_all_orders_for_user() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let other_item_id = Uuid::new_v4();
+        let market_maker = Uuid::new_v4();
+
+        let bid = order_book
+            .add_order(CreateOrderRequest {
+                user_id: market_maker,
+                ..limit_order_request(item_id, OrderSide::Buy, "9.0", "10.0")
+            })
+            .unwrap();
+        let ask = order_book
+            .add_order(CreateOrderRequest {
+                user_id: market_maker,
+                ..limit_order_request(other_item_id, OrderSide::Sell, "11.0", "10.0")
+            })
+            .unwrap();
+        let other_user_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "12.0",
+                "10.0",
+            ))
+            .unwrap();
+
+        let cancelled = order_book.cancel_all_for_user(market_maker);
+
+        assert_eq!(cancelled, vec![bid.id, ask.id]);
+        assert_eq!(order_book.best_bid(item_id), None);
+        assert_eq!(order_book.best_ask(other_item_id), None);
+        assert!(matches!(
+            order_book
+                .get_order_by_id(other_user_order.id)
+                .unwrap()
+                .status,
+            OrderStatus::Open
+        ));
+        assert_eq!(
+            order_book.best_ask(item_id),
+            Some(Decimal::from_str("12.0").unwrap())
+        );
+        assert!(order_book.cancel_all_for_user(market_maker).is_empty());
+    }
+
+    #[test]
+    fn should_expire_open_and_partially_filled_orders_of_disconnected_user() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let trader = Uuid::new_v4();
+        let events: Arc<Mutex<Vec<OrderEvent>>> = Arc::new(Mutex::new(Vec::new()));
+        let listener_events = Arc::clone(&events);
+        order_book.set_event_listener(Box::new(move |event| {
+            listener_events.lock().unwrap().push(event.clone());
+        }));
+
+        let partial_bid = order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "50.0")
+            })
+            .unwrap();
+        let open_ask = order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                ..limit_order_request(item_id, OrderSide::Sell, "12.0", "50.0")
+            })
+            .unwrap();
+        let filled_bid = order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                ..limit_order_request(item_id, OrderSide::Buy, "11.0", "5.0")
+            })
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "25.0",
+            ))
+            .unwrap();
+        let other_user_bid = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "8.0", "10.0"))
+            .unwrap();
+        events.lock().unwrap().clear();
+
+        let expired = order_book.expire_user_orders(trader);
+
+        assert_eq!(expired, vec![partial_bid.id, open_ask.id]);
+        let partial_bid = order_book.get_order_by_id(partial_bid.id).unwrap();
+        assert!(matches!(partial_bid.status, OrderStatus::Cancelled));
+        assert_eq!(
+            partial_bid.quantity_filled,
+            Decimal::from_str("20.0").unwrap()
+        );
+        assert!(matches!(
+            order_book.get_order_by_id(filled_bid.id).unwrap().status,
+            OrderStatus::Closed
+        ));
+        assert_eq!(order_book.best_ask(item_id), None);
+        assert_eq!(order_book.best_bid(item_id), Some(other_user_bid.price));
+        assert!(
+            events
+                .lock()
+                .unwrap()
+                .iter()
+                .all(|event| matches!(event, OrderEvent::Expired { .. }))
+        );
+        assert!(order_book.expire_user_orders(trader).is_empty());
+    }
+
+    #[test]
+    fn should_leave_no_empty_book_entries_after_cancels() {
+        let mut order_book = OrderBookService::new();
+        let item_ids = [Uuid::new_v4(), Uuid::new_v4()];
+
+        let mut order_ids = Vec::new();
+        for item_id in item_ids {
+            for (side, price) in [
+                (OrderSide::Buy, "9.0"),
+                (OrderSide::Buy, "8.0"),
+                (OrderSide::Sell, "11.0"),
+                (OrderSide::Sell, "12.0"),
+            ] {
+                for _ in 0..3 {
+                    let order = order_book
+                        .add_order(limit_order_request(item_id, side, price, "5.0"))
+                        .unwrap();
+                    order_ids.push(order.id);
+                }
+            }
+            let stop_order = order_book
+                .add_order(CreateOrderRequest {
+                    order_type: OrderType::Stop {
+                        trigger: Decimal::from_str("13.0").unwrap(),
+                    },
+                    ..limit_order_request(item_id, OrderSide::Buy, "0.0", "5.0")
+                })
+                .unwrap();
+            order_ids.push(stop_order.id);
+        }
+
+        for order_id in order_ids {
+            assert!(order_book.cancel_order(order_id));
+        }
+
+        order_book.debug_assert_invariants();
+        assert!(order_book.is_empty());
+        for item_id in item_ids {
+            assert!(order_book.depth(item_id, 5).bids.is_empty());
+            assert!(order_book.pending_orders(item_id).is_empty());
+        }
+    }
+
+    #[test]
+    fn should_compact_terminal_orders_without_retained_trades() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            max_trades: Some(1),
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+        let idempotency_key = Uuid::new_v4();
+
+        let cancelled_order = order_book
+            .add_order(CreateOrderRequest {
+                idempotency_key: Some(idempotency_key),
+                ..limit_order_request(item_id, OrderSide::Buy, "9.0", "5.0")
+            })
+            .unwrap();
+        assert!(order_book.cancel_order(cancelled_order.id));
+        let evicted_ask = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+        let evicted_bid = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+        let retained_ask = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "11.0", "2.0"))
+            .unwrap();
+        let retained_bid = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "11.0", "5.0"))
+            .unwrap();
+
+        assert_eq!(order_book.compact(), (3, 1));
+
+        for order_id in [cancelled_order.id, evicted_ask.id, evicted_bid.id] {
+            assert!(order_book.get_order_by_id(order_id).is_none());
+        }
+        for order_id in [retained_ask.id, retained_bid.id] {
+            assert!(order_book.get_order_by_id(order_id).is_some());
+        }
+        assert_eq!(
+            order_book.total_volume(item_id),
+            Decimal::from_str("7.0").unwrap()
+        );
+        assert_eq!(order_book.compact(), (0, 0));
+
+        let resubmitted_order = order_book
+            .add_order(CreateOrderRequest {
+                idempotency_key: Some(idempotency_key),
+                ..limit_order_request(item_id, OrderSide::Buy, "9.0", "5.0")
+            })
+            .unwrap();
+        assert_ne!(resubmitted_order.id, cancelled_order.id);
+        assert!(matches!(resubmitted_order.status, OrderStatus::Open));
+    }
+
+    #[test]
+    fn should_cancel_all_orders_for_item() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let other_item_id = Uuid::new_v4();
+
+        let bid = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "10.0"))
+            .unwrap();
+        let ask = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "11.0",
+                "10.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                other_item_id,
+                OrderSide::Buy,
+                "9.0",
+                "10.0",
+            ))
+            .unwrap();
+
+        let cancelled = order_book.cancel_all_for_item(item_id);
+
+        assert_eq!(cancelled, vec![bid.id, ask.id]);
+        assert_eq!(
+            order_book.resting_orders(item_id, OrderSide::Buy).count(),
+            0
+        );
+        assert_eq!(
+            order_book.resting_orders(item_id, OrderSide::Sell).count(),
+            0
+        );
+        assert_eq!(
+            order_book.best_bid(other_item_id),
+            Some(Decimal::from_str("9.0").unwrap())
+        );
+    }
+
+    #[test]
+    fn should_apply_configured_market_deviation() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            max_market_deviation: Decimal::from_str("0.01").unwrap(),
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "100.0",
+                "10.0",
+            ))
+            .unwrap();
+
+        let too_far = order_book.add_order(CreateOrderRequest {
+            order_type: OrderType::Market,
+            ..limit_order_request(item_id, OrderSide::Buy, "98.0", "1.0")
+        });
+        let error = too_far.unwrap_err();
+        assert!(matches!(
+            error,
+            OrderError::MarketPriceTooFar { max_deviation, .. }
+                if max_deviation == Decimal::from_str("0.01").unwrap()
+        ));
+        assert!(error.to_string().contains("more than 1% away"));
+
+        let within_band = order_book
+            .add_order(CreateOrderRequest {
+                order_type: OrderType::Market,
+                ..limit_order_request(item_id, OrderSide::Buy, "99.5", "1.0")
+            })
+            .unwrap();
+        assert!(matches!(within_band.status, OrderStatus::Closed));
+        assert_eq!(order_book.trades.len(), 1);
+    }
+
+    #[test]
+    fn should_compute_stable_book_checksum() {
+        let build_book = || {
+            let mut order_book = OrderBookService::new();
+            let item_id = Uuid::new_v4();
+            for (side, price, quantity) in [
+                (OrderSide::Buy, "9.0", "10.0"),
+                (OrderSide::Buy, "8.5", "4.0"),
+                (OrderSide::Sell, "10.0", "7.0"),
+                (OrderSide::Sell, "10.5", "3.0"),
+            ] {
+                order_book
+                    .add_order(limit_order_request(item_id, side, price, quantity))
+                    .unwrap();
+            }
+            (order_book, item_id)
+        };
+
+        let (mut order_book, item_id) = build_book();
+        let (identical_book, identical_item_id) = build_book();
+        let checksum = order_book.book_checksum(item_id, 10);
+        assert_eq!(
+            checksum,
+            identical_book.book_checksum(identical_item_id, 10)
+        );
+        assert_eq!(checksum, order_book.book_checksum(item_id, 10));
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "2.0"))
+            .unwrap();
+        assert_ne!(order_book.book_checksum(item_id, 10), checksum);
+    }
+
+    #[test]
+    fn should_sweep_levels_with_unprotected_market_order() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        for (price, quantity) in [("10.0", "5.0"), ("11.0", "5.0"), ("15.0", "5.0")] {
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    OrderSide::Sell,
+                    price,
+                    quantity,
+                ))
+                .unwrap();
+        }
+
+        let market_order = order_book
+            .add_order(CreateOrderRequest {
+                order_type: OrderType::Market,
+                price: None,
+                ..limit_order_request(item_id, OrderSide::Buy, "0.0", "12.0")
+            })
+            .unwrap();
+
+        assert!(matches!(market_order.status, OrderStatus::Closed));
+        assert_eq!(
+            market_order.quantity_filled,
+            Decimal::from_str("12.0").unwrap()
+        );
+        let trade_prices: Vec<Decimal> =
+            order_book.trades.iter().map(|trade| trade.price).collect();
+        assert_eq!(
+            trade_prices,
+            vec![
+                Decimal::from_str("10.0").unwrap(),
+                Decimal::from_str("11.0").unwrap(),
+                Decimal::from_str("15.0").unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_stop_market_order_at_price_band() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        for (price, quantity) in [("10.0", "5.0"), ("10.5", "5.0"), ("10.6", "5.0")] {
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    OrderSide::Sell,
+                    price,
+                    quantity,
+                ))
+                .unwrap();
+        }
+
+        let market_order = order_book
+            .add_order(CreateOrderRequest {
+                order_type: OrderType::Market,
+                price: Some(Decimal::from_str("10.0").unwrap()),
+                ..limit_order_request(item_id, OrderSide::Buy, "0.0", "15.0")
+            })
+            .unwrap();
+
+        assert!(matches!(market_order.status, OrderStatus::Cancelled));
+        assert_eq!(
+            market_order.quantity_filled,
+            Decimal::from_str("10.0").unwrap()
+        );
+        assert_eq!(market_order.quantity, Decimal::from_str("15.0").unwrap());
+        assert_eq!(
+            order_book.trades.last().unwrap().price,
+            Decimal::from_str("10.5").unwrap()
+        );
+        assert_eq!(
+            order_book.best_level(item_id, OrderSide::Sell),
+            Some((
+                Decimal::from_str("10.6").unwrap(),
+                Decimal::from_str("5.0").unwrap()
+            ))
+        );
+    }
+
+    #[test]
+    fn should_reject_limit_order_without_price() {
+        let mut order_book = OrderBookService::new();
+        let result = order_book.add_order(CreateOrderRequest {
+            price: None,
+            ..limit_order_request(Uuid::new_v4(), OrderSide::Buy, "10.0", "5.0")
+        });
+        assert!(matches!(result, Err(OrderError::MissingPrice)));
+    }
+
+    #[test]
+    fn should_walk_multiple_price_levels_for_large_order() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        for price in ["10.0", "11.0", "12.0"] {
+            order_book
+                .add_order(limit_order_request(item_id, OrderSide::Sell, price, "50.0"))
+                .unwrap();
+        }
+
+        let buy_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Buy,
+                "12.0",
+                "150.0",
+            ))
+            .unwrap();
+
+        assert!(matches!(buy_order.status, OrderStatus::Closed));
+        assert_eq!(order_book.trades.len(), 3);
+        for (trade, price) in order_book.trades.iter().zip(["10.0", "11.0", "12.0"]) {
+            assert_eq!(trade.price, Decimal::from_str(price).unwrap());
+            assert_eq!(trade.quantity, Decimal::from_str("50.0").unwrap());
+        }
+        assert_eq!(order_book.best_ask(item_id), None);
+    }
+
+    #[test]
+    fn should_display_order_and_trade_summaries() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let sell_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.5", "5.0"))
+            .unwrap();
+
+        let order_line = sell_order.to_string();
+        assert!(order_line.contains(&sell_order.id.to_string()));
+        assert!(order_line.contains("Sell"));
+        assert!(order_line.contains("10.5"));
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.5", "2.0"))
+            .unwrap();
+        let trade_line = order_book.trades[0].to_string();
+        assert!(trade_line.contains(&order_book.trades[0].id.to_string()));
+        assert!(trade_line.contains("10.5"));
+
+        let summary = order_book.summary();
+        assert!(summary.contains("Open orders: 1, trades: 1"));
+        assert!(summary.contains(&format!(
+            "Item {}: traded volume 2.0 across 1 trades",
+            item_id
+        )));
+    }
+
+    #[test]
+    fn should_report_book_stats() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        assert_eq!(order_book.stats(item_id).open_orders, 0);
+        assert_eq!(order_book.stats(item_id).last_trade_price, None);
+
+        for (side, price, quantity) in [
+            (OrderSide::Buy, "9.0", "10.0"),
+            (OrderSide::Buy, "9.0", "5.0"),
+            (OrderSide::Buy, "8.0", "4.0"),
+            (OrderSide::Sell, "11.0", "6.0"),
+            (OrderSide::Sell, "12.0", "3.0"),
+        ] {
+            order_book
+                .add_order(limit_order_request(item_id, side, price, quantity))
+                .unwrap();
+        }
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "9.0", "2.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                Uuid::new_v4(),
+                OrderSide::Buy,
+                "9.0",
+                "1.0",
+            ))
+            .unwrap();
+
+        let stats = order_book.stats(item_id);
+        assert_eq!(stats.open_orders, 5);
+        assert_eq!(stats.bid_levels, 2);
+        assert_eq!(stats.ask_levels, 2);
+        assert_eq!(stats.total_bid_qty, Decimal::from_str("17.0").unwrap());
+        assert_eq!(stats.total_ask_qty, Decimal::from_str("9.0").unwrap());
+        assert_eq!(
+            stats.last_trade_price,
+            Some(Decimal::from_str("9.0").unwrap())
+        );
+        assert_eq!(stats.trade_count, 1);
+    }
+
+    #[test]
+    fn should_skip_filled_resting_order_and_fill_the_rest_of_the_level() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let stale_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+        let fillable_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+
+        let order = order_book.get_mutable_order_by_id(stale_order.id).unwrap();
+        order.quantity_filled = order.quantity;
+
+        let buy_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "3.0"))
+            .unwrap();
+
+        assert!(matches!(buy_order.status, OrderStatus::Closed));
+        assert_eq!(order_book.trades.len(), 1);
+        assert_eq!(order_book.trades[0].sell_order_id, fillable_order.id);
+        let asks: Vec<Uuid> = order_book
+            .resting_orders(item_id, OrderSide::Sell)
+            .map(|order| order.id)
+            .collect();
+        assert_eq!(asks, vec![fillable_order.id]);
+    }
+
+    #[test]
+    fn should_submit_batch_in_order() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let orders = order_book
+            .add_orders_atomic(vec![
+                limit_order_request(item_id, OrderSide::Buy, "9.0", "5.0"),
+                limit_order_request(item_id, OrderSide::Sell, "11.0", "5.0"),
+            ])
+            .unwrap();
+
+        assert_eq!(orders.len(), 2);
+        assert!(orders[0].seq < orders[1].seq);
+        assert_eq!(
+            order_book.best_bid(item_id),
+            Some(Decimal::from_str("9.0").unwrap())
+        );
+        assert_eq!(
+            order_book.best_ask(item_id),
+            Some(Decimal::from_str("11.0").unwrap())
+        );
+    }
+
+    #[test]
+    fn should_reject_whole_batch_when_one_request_is_invalid() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let result = order_book.add_orders_atomic(vec![
+            limit_order_request(item_id, OrderSide::Buy, "9.0", "5.0"),
+            limit_order_request(item_id, OrderSide::Sell, "11.0", "0.0"),
+            limit_order_request(item_id, OrderSide::Sell, "12.0", "5.0"),
+        ]);
+
+        assert!(matches!(result, Err(OrderError::NonPositiveQuantity)));
+        assert!(order_book.get_orders().is_empty());
+        assert_eq!(order_book.best_bid(item_id), None);
+    }
+
+    #[test]
+    fn should_cap_open_orders_per_user() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            max_open_orders_per_user: Some(2),
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+        let trader = Uuid::new_v4();
+        let trader_order = |price: &str| CreateOrderRequest {
+            user_id: trader,
+            ..limit_order_request(item_id, OrderSide::Buy, price, "5.0")
+        };
+
+        let first_order = order_book.add_order(trader_order("9.0")).unwrap();
+        order_book.add_order(trader_order("8.0")).unwrap();
+
+        let result = order_book.add_order(trader_order("7.0"));
+        assert!(matches!(
+            result,
+            Err(OrderError::OpenOrderLimitReached { limit: 2 })
+        ));
+        assert!(
+            order_book
+                .add_order(limit_order_request(item_id, OrderSide::Buy, "7.0", "5.0"))
+                .is_ok()
+        );
+
+        order_book.cancel_order(first_order.id);
+        assert!(order_book.add_order(trader_order("7.0")).is_ok());
+        assert!(matches!(
+            order_book.add_orders_atomic(vec![trader_order("6.0")]),
+            Err(OrderError::OpenOrderLimitReached { limit: 2 })
+        ));
+    }
+
+    #[test]
+    fn should_reject_batch_that_would_exceed_open_order_cap() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            max_open_orders_per_user: Some(2),
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+        let trader = Uuid::new_v4();
+
+        let result = order_book.add_orders_atomic(
+            ["9.0", "8.0", "7.0"]
+                .into_iter()
+                .map(|price| CreateOrderRequest {
+                    user_id: trader,
+                    ..limit_order_request(item_id, OrderSide::Buy, price, "5.0")
+                })
+                .collect(),
+        );
+
+        assert!(matches!(
+            result,
+            Err(OrderError::OpenOrderLimitReached { limit: 2 })
+        ));
+        assert!(order_book.get_orders().is_empty());
+    }
+
+    #[test]
+    fn should_compute_24h_ticker_from_in_window_trades() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        for (price, quantity) in [
+            ("20.0", "1.0"),
+            ("10.0", "2.0"),
+            ("12.0", "3.0"),
+            ("11.0", "4.0"),
+        ] {
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    OrderSide::Sell,
+                    price,
+                    quantity,
+                ))
+                .unwrap();
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    OrderSide::Buy,
+                    price,
+                    quantity,
+                ))
+                .unwrap();
+        }
+
+        let now = Utc::now();
+        order_book.trades[0].timestamp = now - Duration::hours(30);
+        order_book.trades[1].timestamp = now - Duration::hours(25);
+
+        assert_eq!(
+            order_book.last_price(item_id),
+            Some(Decimal::from_str("11.0").unwrap())
+        );
+        assert_eq!(
+            order_book.high_24h(item_id, now),
+            Some(Decimal::from_str("12.0").unwrap())
+        );
+        assert_eq!(
+            order_book.low_24h(item_id, now),
+            Some(Decimal::from_str("11.0").unwrap())
+        );
+        assert_eq!(
+            order_book.volume_24h(item_id, now),
+            Decimal::from_str("7.0").unwrap()
+        );
+
+        let next_week = now + Duration::days(7);
+        assert_eq!(order_book.high_24h(item_id, next_week), None);
+        assert_eq!(order_book.volume_24h(item_id, next_week), Decimal::ZERO);
+        assert_eq!(order_book.last_price(Uuid::new_v4()), None);
+    }
+
+    #[test]
+    fn should_reject_gtd_order_that_has_already_expired() {
+        let mut order_book = OrderBookService::new();
+        let clock = MockClock::new(Utc::now());
+        order_book.set_clock(Arc::new(clock.clone()));
+        let item_id = Uuid::new_v4();
+
+        for expires_at in [clock.now() - Duration::hours(1), clock.now()] {
+            assert!(matches!(
+                order_book.add_order(CreateOrderRequest {
+                    time_in_force: TimeInForce::GTD(expires_at),
+                    ..limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0")
+                }),
+                Err(OrderError::AlreadyExpired)
+            ));
+        }
+        assert!(order_book.is_empty());
+
+        let expires_at = clock.now() + Duration::hours(1);
+        let gtd_order = order_book
+            .add_order(CreateOrderRequest {
+                time_in_force: TimeInForce::GTD(expires_at),
+                ..limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0")
+            })
+            .unwrap();
+        clock.set(expires_at);
+
+        let buy_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+
+        assert!(matches!(buy_order.status, OrderStatus::Open));
+        assert!(order_book.trades.is_empty());
+        assert_eq!(order_book.expire_orders(expires_at), vec![gtd_order.id]);
+    }
+
+    #[test]
+    fn should_expire_gtd_order_at_its_date() {
+        let mut order_book = OrderBookService::new();
+        let now = Utc::now();
+        let expires_at = now + Duration::hours(1);
+
+        let gtd_order = order_book
+            .add_order(CreateOrderRequest {
+                time_in_force: TimeInForce::GTD(expires_at),
+                ..limit_order_request(Uuid::new_v4(), OrderSide::Buy, "10.0", "5.0")
+            })
+            .unwrap();
+        assert_eq!(gtd_order.expires_at, Some(expires_at));
+
+        assert!(
+            order_book
+                .expire_orders(now + Duration::minutes(30))
+                .is_empty()
+        );
+        assert!(matches!(
+            order_book.get_order_by_id(gtd_order.id).unwrap().status,
+            OrderStatus::Open
+        ));
+
+        assert_eq!(
+            order_book.expire_orders(now + Duration::hours(2)),
+            vec![gtd_order.id]
+        );
+        assert!(matches!(
+            order_book.get_order_by_id(gtd_order.id).unwrap().status,
+            OrderStatus::Cancelled
+        ));
+    }
+
+    #[test]
+    fn should_trim_reduce_only_sell_to_long_position() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let trader = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "4.0"))
+            .unwrap();
+        order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "4.0")
+            })
+            .unwrap();
+
+        let reduce_only_sell = order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                reduce_only: true,
+                ..limit_order_request(item_id, OrderSide::Sell, "11.0", "10.0")
+            })
+            .unwrap();
+
+        assert_eq!(reduce_only_sell.quantity, Decimal::from_str("4.0").unwrap());
+        assert_eq!(
+            order_book.depth(item_id, 1).asks,
+            vec![(
+                Decimal::from_str("11.0").unwrap(),
+                Decimal::from_str("4.0").unwrap()
+            )]
+        );
+    }
+
+    #[test]
+    fn should_reject_reduce_only_order_that_would_increase_position() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let trader = Uuid::new_v4();
+
+        let flat = order_book.add_order(CreateOrderRequest {
+            user_id: trader,
+            reduce_only: true,
+            ..limit_order_request(item_id, OrderSide::Sell, "10.0", "1.0")
+        });
+        assert!(matches!(flat, Err(OrderError::ReduceOnlyWouldIncrease)));
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "4.0"))
+            .unwrap();
+        order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "4.0")
+            })
+            .unwrap();
+
+        let adding_to_long = order_book.add_order(CreateOrderRequest {
+            user_id: trader,
+            reduce_only: true,
+            ..limit_order_request(item_id, OrderSide::Buy, "9.0", "1.0")
+        });
+        assert!(matches!(
+            adding_to_long,
+            Err(OrderError::ReduceOnlyWouldIncrease)
+        ));
+    }
+
+    #[test]
+    fn should_net_position_across_buys_and_sells() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let trader = Uuid::new_v4();
+        let counterparty = Uuid::new_v4();
+
+        for (trader_side, quantity) in [
+            (OrderSide::Buy, "10.0"),
+            (OrderSide::Sell, "4.0"),
+            (OrderSide::Buy, "1.5"),
+        ] {
+            let counterparty_side = match trader_side {
+                OrderSide::Buy => OrderSide::Sell,
+                OrderSide::Sell => OrderSide::Buy,
+            };
+            order_book
+                .add_order(CreateOrderRequest {
+                    user_id: counterparty,
+                    ..limit_order_request(item_id, counterparty_side, "10.0", quantity)
+                })
+                .unwrap();
+            order_book
+                .add_order(CreateOrderRequest {
+                    user_id: trader,
+                    ..limit_order_request(item_id, trader_side, "10.0", quantity)
+                })
+                .unwrap();
+        }
+
+        assert_eq!(
+            order_book.position(trader, item_id),
+            Decimal::from_str("7.5").unwrap()
+        );
+        assert_eq!(
+            order_book.position(counterparty, item_id),
+            Decimal::from_str("-7.5").unwrap()
+        );
+        assert_eq!(order_book.position(trader, Uuid::new_v4()), Decimal::ZERO);
+    }
+
+    fn trade_against_counterparty(
+        order_book: &mut OrderBookService,
+        item_id: Uuid,
+        trader: Uuid,
+        counterparty: Uuid,
+        fills: &[(OrderSide, &str, &str)],
+    ) {
+        for (trader_side, price, quantity) in fills {
+            let counterparty_side = match trader_side {
+                OrderSide::Buy => OrderSide::Sell,
+                OrderSide::Sell => OrderSide::Buy,
+            };
+            order_book
+                .add_order(CreateOrderRequest {
+                    user_id: counterparty,
+                    ..limit_order_request(item_id, counterparty_side, price, quantity)
+                })
+                .unwrap();
+            order_book
+                .add_order(CreateOrderRequest {
+                    user_id: trader,
+                    ..limit_order_request(item_id, *trader_side, price, quantity)
+                })
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn should_realize_pnl_of_round_trip() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let trader = Uuid::new_v4();
+        let counterparty = Uuid::new_v4();
+
+        trade_against_counterparty(
+            &mut order_book,
+            item_id,
+            trader,
+            counterparty,
+            &[(OrderSide::Buy, "10.0", "10.0")],
+        );
+        assert_eq!(order_book.realized_pnl(trader, item_id), Decimal::ZERO);
+
+        trade_against_counterparty(
+            &mut order_book,
+            item_id,
+            trader,
+            counterparty,
+            &[(OrderSide::Sell, "12.0", "10.0")],
+        );
+        assert_eq!(
+            order_book.realized_pnl(trader, item_id),
+            Decimal::from_str("20.0").unwrap()
+        );
+        assert_eq!(
+            order_book.realized_pnl(counterparty, item_id),
+            Decimal::from_str("-20.0").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_realize_pnl_against_weighted_average_cost() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let trader = Uuid::new_v4();
+        let counterparty = Uuid::new_v4();
+
+        trade_against_counterparty(
+            &mut order_book,
+            item_id,
+            trader,
+            counterparty,
+            &[
+                (OrderSide::Buy, "10.0", "10.0"),
+                (OrderSide::Buy, "12.0", "10.0"),
+                (OrderSide::Sell, "13.0", "5.0"),
+                (OrderSide::Sell, "9.0", "25.0"),
+                (OrderSide::Buy, "8.0", "10.0"),
+            ],
+        );
+
+        // Long 20 at 11: +10 on 5 at 13, -30 on 15 at 9, then short 10 at 9
+        // covered at 8 for +10.
+        assert_eq!(
+            order_book.realized_pnl(trader, item_id),
+            Decimal::from_str("-10.0").unwrap()
+        );
+        assert_eq!(order_book.position(trader, item_id), Decimal::ZERO);
+    }
+
+    #[test]
+    fn should_aggregate_remaining_quantity_at_best_level() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        assert_eq!(order_book.best_level(item_id, OrderSide::Sell), None);
+
+        for quantity in ["5.0", "7.0", "3.0"] {
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    OrderSide::Sell,
+                    "10.0",
+                    quantity,
+                ))
+                .unwrap();
+        }
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "11.0",
+                "50.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "2.0"))
+            .unwrap();
+
+        assert_eq!(
+            order_book.best_level(item_id, OrderSide::Sell),
+            Some((
+                Decimal::from_str("10.0").unwrap(),
+                Decimal::from_str("13.0").unwrap()
+            ))
+        );
+        assert_eq!(order_book.best_level(item_id, OrderSide::Buy), None);
+    }
+
+    #[test]
+    fn should_reject_orders_while_item_is_halted() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let resting_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+
+        order_book.halt_item(item_id);
+        assert!(order_book.is_halted(item_id));
+
+        let result =
+            order_book.add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"));
+        assert!(matches!(result, Err(OrderError::ItemHalted)));
+        assert!(order_book.trades.is_empty());
+        assert!(matches!(
+            order_book.get_order_by_id(resting_order.id).unwrap().status,
+            OrderStatus::Open
+        ));
+        assert_eq!(
+            order_book.best_ask(item_id),
+            Some(Decimal::from_str("10.0").unwrap())
+        );
+
+        let other_item = order_book.add_order(limit_order_request(
+            Uuid::new_v4(),
+            OrderSide::Buy,
+            "10.0",
+            "5.0",
+        ));
+        assert!(other_item.is_ok());
+    }
+
+    #[test]
+    fn should_halt_item_when_trade_breaches_circuit_breaker() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            circuit_breaker: Some(Decimal::from_str("0.10").unwrap()),
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "1.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "1.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.5", "5.0"))
+            .unwrap();
+        let gapped_ask = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "12.0", "5.0"))
+            .unwrap();
+
+        let buy_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "12.0", "10.0"))
+            .unwrap();
+
+        assert!(order_book.is_halted(item_id));
+        assert!(matches!(buy_order.status, OrderStatus::Cancelled));
+        assert_eq!(buy_order.quantity_filled, Decimal::from_str("5.0").unwrap());
+        assert_eq!(order_book.trades.len(), 2);
+        assert_eq!(
+            order_book.last_price(item_id),
+            Some(Decimal::from_str("10.5").unwrap())
+        );
+        assert!(matches!(
+            order_book.get_order_by_id(gapped_ask.id).unwrap().status,
+            OrderStatus::Open
+        ));
+        assert!(!order_book.is_crossed(item_id));
+        assert!(matches!(
+            order_book.add_order(limit_order_request(item_id, OrderSide::Buy, "12.0", "1.0")),
+            Err(OrderError::ItemHalted)
+        ));
+    }
+
+    #[test]
+    fn should_reject_reprice_across_circuit_breaker_band() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            circuit_breaker: Some(Decimal::from_str("0.10").unwrap()),
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "1.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "1.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "12.0", "5.0"))
+            .unwrap();
+        let first_bid = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.5", "5.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.5", "5.0"))
+            .unwrap();
+
+        assert!(matches!(
+            order_book.amend_order(first_bid.id, Some(Decimal::from_str("12.0").unwrap()), None),
+            Err(OrderError::RepriceBreachesCircuitBreaker)
+        ));
+        assert!(
+            order_book
+                .update_order_price(first_bid.id, Decimal::from_str("12.0").unwrap())
+                .is_none()
+        );
+
+        assert!(!order_book.is_halted(item_id));
+        assert_eq!(order_book.trades.len(), 1);
+        let resting_bid = order_book.get_order_by_id(first_bid.id).unwrap();
+        assert!(matches!(resting_bid.status, OrderStatus::Open));
+        assert_eq!(resting_bid.price, Decimal::from_str("9.5").unwrap());
+        assert_eq!(
+            order_book
+                .resting_orders(item_id, OrderSide::Buy)
+                .next()
+                .map(|order| order.id),
+            Some(first_bid.id)
+        );
+
+        let repriced_bid = order_book
+            .amend_order(first_bid.id, Some(Decimal::from_str("10.5").unwrap()), None)
+            .unwrap();
+        assert_eq!(repriced_bid.price, Decimal::from_str("10.5").unwrap());
+        assert!(matches!(repriced_bid.status, OrderStatus::Open));
+    }
+
+    #[test]
+    fn should_accept_orders_after_item_is_resumed() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+
+        order_book.halt_item(item_id);
+        order_book.resume_item(item_id);
+        assert!(!order_book.is_halted(item_id));
+
+        let buy_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+        assert!(matches!(buy_order.status, OrderStatus::Closed));
+        assert_eq!(order_book.trades.len(), 1);
+    }
+
+    #[test]
+    fn should_uncross_auction_at_volume_maximizing_price() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book.start_auction(item_id);
+
+        let mut bids = Vec::new();
+        for (price, quantity) in [("12.0", "10.0"), ("11.0", "10.0"), ("10.0", "10.0")] {
+            bids.push(
+                order_book
+                    .add_order(limit_order_request(
+                        item_id,
+                        OrderSide::Buy,
+                        price,
+                        quantity,
+                    ))
+                    .unwrap(),
+            );
+        }
+        let mut asks = Vec::new();
+        for (price, quantity) in [("9.0", "5.0"), ("10.0", "10.0"), ("11.0", "10.0")] {
+            asks.push(
+                order_book
+                    .add_order(limit_order_request(
+                        item_id,
+                        OrderSide::Sell,
+                        price,
+                        quantity,
+                    ))
+                    .unwrap(),
+            );
+        }
+        assert!(order_book.trades.is_empty());
+
+        let result = order_book.run_auction(item_id).unwrap();
+
+        assert_eq!(result.clearing_price, Decimal::from_str("11.0").unwrap());
+        assert_eq!(result.matched_volume, Decimal::from_str("20.0").unwrap());
+        assert!(
+            order_book
+                .trades
+                .iter()
+                .all(|trade| trade.price == result.clearing_price)
+        );
+        let traded: Decimal = order_book.trades.iter().map(|trade| trade.quantity).sum();
+        assert_eq!(traded, result.matched_volume);
+        assert!(matches!(
+            order_book.get_order_by_id(bids[0].id).unwrap().status,
+            OrderStatus::Closed
+        ));
+        let partially_filled_ask = order_book.get_order_by_id(asks[2].id).unwrap();
+        assert!(matches!(
+            partially_filled_ask.status,
+            OrderStatus::PartiallyFilled
+        ));
+        assert_eq!(
+            partially_filled_ask.quantity_filled,
+            Decimal::from_str("5.0").unwrap()
+        );
+        assert!(!order_book.is_in_auction(item_id));
+        assert!(order_book.best_bid(item_id) < order_book.best_ask(item_id));
+    }
+
+    #[test]
+    fn should_return_none_when_auction_book_does_not_cross() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "10.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "10.0",
+            ))
+            .unwrap();
+
+        assert!(order_book.run_auction(item_id).is_none());
+        assert!(order_book.trades.is_empty());
+    }
+
+    #[test]
+    fn should_reject_immediate_orders_during_auction() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book.start_auction(item_id);
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "10.0",
+            ))
+            .unwrap();
+
+        let ioc = order_book.add_order(CreateOrderRequest {
+            time_in_force: TimeInForce::IOC,
+            ..limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0")
+        });
+        assert!(matches!(ioc, Err(OrderError::AuctionInProgress)));
+    }
+
+    #[test]
+    fn should_clone_book_into_structurally_equal_copy() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        for (side, price, quantity) in [
+            (OrderSide::Sell, "10.0", "5.0"),
+            (OrderSide::Sell, "11.0", "5.0"),
+            (OrderSide::Buy, "9.0", "5.0"),
+            (OrderSide::Buy, "10.0", "2.0"),
+        ] {
+            order_book
+                .add_order(limit_order_request(item_id, side, price, quantity))
+                .unwrap();
+        }
+        order_book
+            .add_order(CreateOrderRequest {
+                order_type: OrderType::Stop {
+                    trigger: Decimal::from_str("12.0").unwrap(),
+                },
+                ..limit_order_request(item_id, OrderSide::Buy, "0.0", "1.0")
+            })
+            .unwrap();
+
+        let mut cloned_book = order_book.clone();
+        assert!(order_book.structurally_eq(&cloned_book));
+
+        cloned_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "1.0"))
+            .unwrap();
+        assert!(!order_book.structurally_eq(&cloned_book));
+        assert_eq!(order_book.trades.len(), 1);
+    }
+
+    #[test]
+    fn should_reduce_resting_order_without_losing_priority() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let first_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "50.0",
+            ))
+            .unwrap();
+        let second_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "50.0",
+            ))
+            .unwrap();
+
+        let reduced_order = order_book
+            .reduce_order(first_order.id, Decimal::from_str("30.0").unwrap())
+            .unwrap();
+        assert_eq!(reduced_order.quantity, Decimal::from_str("20.0").unwrap());
+        assert!(matches!(reduced_order.status, OrderStatus::Open));
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "20.0"))
+            .unwrap();
+
+        assert_eq!(order_book.trades.len(), 1);
+        assert_eq!(order_book.trades[0].sell_order_id, first_order.id);
+        assert!(matches!(
+            order_book.get_order_by_id(first_order.id).unwrap().status,
+            OrderStatus::Closed
+        ));
+        assert_eq!(
+            order_book
+                .get_order_by_id(second_order.id)
+                .unwrap()
+                .quantity_filled,
+            Decimal::ZERO
+        );
+    }
+
+    #[test]
+    fn should_cancel_order_reduced_by_its_remaining_quantity() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "50.0"))
+            .unwrap();
+
+        let reduced_order = order_book
+            .reduce_order(order.id, Decimal::from_str("50.0").unwrap())
+            .unwrap();
+
+        assert!(matches!(reduced_order.status, OrderStatus::Cancelled));
+        assert_eq!(order_book.best_bid(item_id), None);
+        assert!(matches!(
+            order_book.reduce_order(order.id, Decimal::ONE),
+            Err(OrderError::OrderNotActive)
+        ));
+        assert!(matches!(
+            order_book.reduce_order(Uuid::new_v4(), Decimal::ONE),
+            Err(OrderError::OrderNotFound)
+        ));
+    }
+
+    #[test]
+    fn should_leave_book_uncrossed_after_matching() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "20.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "11.0",
+                "20.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "20.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "12.0", "30.0"))
+            .unwrap();
+
+        assert_eq!(order_book.trades.len(), 2);
+        assert_eq!(
+            order_book.best_bid(item_id),
+            Some(Decimal::from_str("9.0").unwrap())
+        );
+        assert_eq!(
+            order_book.best_ask(item_id),
+            Some(Decimal::from_str("11.0").unwrap())
+        );
+        assert!(!order_book.is_crossed(item_id));
+    }
+
+    #[test]
+    fn should_report_crossed_book_collected_during_auction() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book.start_auction(item_id);
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "20.0",
+            ))
+            .unwrap();
+        assert!(!order_book.is_crossed(item_id));
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "20.0"))
+            .unwrap();
+        assert!(order_book.is_crossed(item_id));
+
+        order_book.run_auction(item_id);
+        assert!(!order_book.is_crossed(item_id));
+    }
+
+    #[test]
+    fn should_compute_mid_and_micro_price_from_top_of_book() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "30.0"))
+            .unwrap();
+        assert_eq!(order_book.mid_price(item_id), None);
+        assert_eq!(order_book.micro_price(item_id), None);
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "100.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "12.0",
+                "10.0",
+            ))
+            .unwrap();
+
+        assert_eq!(
+            order_book.mid_price(item_id),
+            Some(Decimal::from_str("11.0").unwrap())
+        );
+        assert_eq!(
+            order_book.micro_price(item_id),
+            Some(Decimal::from_str("11.5").unwrap())
+        );
+    }
+
+    #[test]
+    fn should_weight_micro_price_by_displayed_iceberg_slice() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(CreateOrderRequest {
+                display_quantity: Some(Decimal::from_str("10.0").unwrap()),
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "300.0")
+            })
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "12.0",
+                "10.0",
+            ))
+            .unwrap();
+
+        assert_eq!(
+            order_book.best_level(item_id, OrderSide::Buy),
+            Some((
+                Decimal::from_str("10.0").unwrap(),
+                Decimal::from_str("10.0").unwrap()
+            ))
+        );
+        assert_eq!(
+            order_book.micro_price(item_id),
+            Some(Decimal::from_str("11.0").unwrap())
+        );
+    }
+
+    #[test]
+    fn should_sequence_fills_of_one_submission_monotonically() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        for price in ["10.0", "10.0", "11.0", "12.0"] {
+            order_book
+                .add_order(limit_order_request(item_id, OrderSide::Sell, price, "10.0"))
+                .unwrap();
+        }
+        let buy_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "12.0", "40.0"))
+            .unwrap();
+
+        assert_eq!(order_book.trades.len(), 4);
+        assert!(
+            order_book
+                .trades
+                .iter()
+                .all(|trade| trade.aggressor_seq == buy_order.seq)
+        );
+        assert!(order_book.trades[0].seq > buy_order.seq);
+        assert!(
+            order_book
+                .trades
+                .windows(2)
+                .all(|pair| pair[0].seq < pair[1].seq)
+        );
+    }
+
+    #[test]
+    fn should_append_trades_in_match_order() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let events: Arc<Mutex<Vec<OrderEvent>>> = Arc::new(Mutex::new(Vec::new()));
+        let listener_events = Arc::clone(&events);
+        order_book.set_event_listener(Box::new(move |event| {
+            listener_events.lock().unwrap().push(event.clone());
+        }));
+
+        let mut resting_order_ids = Vec::new();
+        for price in ["11.0", "10.0", "12.0", "10.0", "11.0"] {
+            let resting_order = order_book
+                .add_order(limit_order_request(item_id, OrderSide::Sell, price, "10.0"))
+                .unwrap();
+            resting_order_ids.push(resting_order.id);
+        }
+        let expected_order = [
+            resting_order_ids[1],
+            resting_order_ids[3],
+            resting_order_ids[0],
+            resting_order_ids[4],
+            resting_order_ids[2],
+        ];
+
+        let outcome = order_book
+            .add_order_with_trades(CreateOrderRequest {
+                time_in_force: TimeInForce::FOK,
+                ..limit_order_request(item_id, OrderSide::Buy, "12.0", "50.0")
+            })
+            .unwrap();
+
+        let matched: Vec<Uuid> = order_book
+            .trades
+            .iter()
+            .map(|trade| trade.sell_order_id)
+            .collect();
+        assert_eq!(matched, expected_order);
+        let outcome_matched: Vec<Uuid> = outcome
+            .trades
+            .iter()
+            .map(|trade| trade.sell_order_id)
+            .collect();
+        assert_eq!(outcome_matched, expected_order);
+        let traded_events: Vec<Uuid> = events
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|event| match event {
+                OrderEvent::Traded(trade) => Some(trade.sell_order_id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(traded_events, expected_order);
+    }
+
+    #[test]
+    fn should_list_only_working_orders_for_user() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let trader = Uuid::new_v4();
+
+        let open_order = order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                ..limit_order_request(item_id, OrderSide::Buy, "9.0", "10.0")
+            })
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "15.0",
+            ))
+            .unwrap();
+        let filled_order = order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0")
+            })
+            .unwrap();
+        let partially_filled_order = order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "20.0")
+            })
+            .unwrap();
+        let cancelled_order = order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                ..limit_order_request(item_id, OrderSide::Buy, "8.0", "10.0")
+            })
+            .unwrap();
+        order_book.cancel_order(cancelled_order.id);
+
+        assert!(matches!(filled_order.status, OrderStatus::Closed));
+        assert!(matches!(
+            partially_filled_order.status,
+            OrderStatus::PartiallyFilled
+        ));
+        let open_order_ids: Vec<Uuid> = order_book
+            .open_orders_for_user(trader)
+            .iter()
+            .map(|order| order.id)
+            .collect();
+        assert_eq!(
+            open_order_ids,
+            vec![open_order.id, partially_filled_order.id]
+        );
+        assert!(order_book.open_orders_for_user(Uuid::new_v4()).is_empty());
+    }
+
+    #[test]
+    fn should_return_original_order_for_repeated_idempotency_key() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let request = CreateOrderRequest {
+            idempotency_key: Some(Uuid::new_v4()),
+            ..limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0")
+        };
+
+        let first_order = order_book.add_order(request.clone()).unwrap();
+        let retried_order = order_book.add_order(request.clone()).unwrap();
+
+        assert_eq!(retried_order.id, first_order.id);
+        assert_eq!(retried_order.seq, first_order.seq);
+        assert_eq!(retried_order.created_at, first_order.created_at);
+        assert_eq!(order_book.orders_by_status(OrderStatus::Open).len(), 1);
+        assert_eq!(
+            order_book.best_level(item_id, OrderSide::Buy),
+            Some((
+                Decimal::from_str("10.0").unwrap(),
+                Decimal::from_str("5.0").unwrap()
+            ))
+        );
+
+        let mut restored_book = OrderBookService::restore(order_book.snapshot());
+        assert_eq!(restored_book.add_order(request).unwrap().id, first_order.id);
+        assert_eq!(restored_book.orders_by_status(OrderStatus::Open).len(), 1);
+    }
+
+    #[test]
+    fn should_honour_idempotency_keys_in_atomic_batch() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let submitted_key = Uuid::new_v4();
+        let batch_key = Uuid::new_v4();
+        let first_order = order_book
+            .add_order(CreateOrderRequest {
+                idempotency_key: Some(submitted_key),
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0")
+            })
+            .unwrap();
+
+        let batch_orders = order_book
+            .add_orders_atomic(vec![
+                CreateOrderRequest {
+                    idempotency_key: Some(submitted_key),
+                    ..limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0")
+                },
+                CreateOrderRequest {
+                    idempotency_key: Some(batch_key),
+                    ..limit_order_request(item_id, OrderSide::Buy, "9.0", "5.0")
+                },
+                CreateOrderRequest {
+                    idempotency_key: Some(batch_key),
+                    ..limit_order_request(item_id, OrderSide::Buy, "9.0", "5.0")
+                },
+            ])
+            .unwrap();
+
+        assert_eq!(batch_orders.len(), 3);
+        assert_eq!(batch_orders[0].id, first_order.id);
+        assert_eq!(batch_orders[2].id, batch_orders[1].id);
+        assert_eq!(order_book.orders_by_status(OrderStatus::Open).len(), 2);
+    }
+
+    #[test]
+    fn should_honour_idempotency_key_on_replace() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let original_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+        let replacement_request = CreateOrderRequest {
+            idempotency_key: Some(Uuid::new_v4()),
+            ..limit_order_request(item_id, OrderSide::Buy, "11.0", "5.0")
+        };
+
+        let replacement = order_book
+            .replace_order(original_order.id, replacement_request.clone())
+            .unwrap();
+        let retried_replacement = order_book
+            .replace_order(original_order.id, replacement_request)
+            .unwrap();
+
+        assert_eq!(retried_replacement.id, replacement.id);
+        assert_eq!(order_book.orders_by_status(OrderStatus::Open).len(), 1);
+        assert_eq!(order_book.orders_by_status(OrderStatus::Cancelled).len(), 1);
+    }
+
+    #[test]
+    fn should_filter_orders_by_status() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let first_bid = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "10.0"))
+            .unwrap();
+        let second_bid = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "8.0", "10.0"))
+            .unwrap();
+        let cancelled_bid = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "7.0", "10.0"))
+            .unwrap();
+        order_book.cancel_order(cancelled_bid.id);
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "12.0", "5.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "12.0", "5.0"))
+            .unwrap();
+
+        let open_bid_ids: Vec<Uuid> = order_book
+            .orders_by_status(OrderStatus::Open)
+            .iter()
+            .map(|order| order.id)
+            .collect();
+        assert_eq!(open_bid_ids, vec![first_bid.id, second_bid.id]);
+        assert_eq!(order_book.orders_by_status(OrderStatus::Closed).len(), 2);
+        assert_eq!(order_book.orders_by_status(OrderStatus::Cancelled).len(), 1);
+        assert!(
+            order_book
+                .orders_by_status(OrderStatus::PartiallyFilled)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn should_average_fill_price_across_levels() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let cheap_sell = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "30.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "12.0",
+                "30.0",
+            ))
+            .unwrap();
+        let buy_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "12.0", "40.0"))
+            .unwrap();
+        let resting_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "10.0"))
+            .unwrap();
+
+        assert_eq!(
+            order_book.average_fill_price(buy_order.id),
+            Some(Decimal::from_str("10.5").unwrap())
+        );
+        assert_eq!(
+            order_book.average_fill_price(cheap_sell.id),
+            Some(Decimal::from_str("10.0").unwrap())
+        );
+        assert_eq!(order_book.average_fill_price(resting_order.id), None);
+    }
+
+    #[test]
+    fn should_report_fill_ratio_of_partially_filled_order() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let sell_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "40.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "10.0"))
+            .unwrap();
+
+        assert_eq!(
+            order_book.fill_ratio(sell_order.id),
+            Some(Decimal::from_str("0.25").unwrap())
+        );
+        assert_eq!(order_book.fill_ratio(Uuid::new_v4()), None);
+    }
+
+    #[test]
+    fn should_keep_requested_quantity_of_partially_filled_market_order() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "40.0",
+            ))
+            .unwrap();
+
+        let (result, market_order) = order_book.add_order_with_result(CreateOrderRequest {
+            order_type: OrderType::Market,
+            price: None,
+            ..limit_order_request(item_id, OrderSide::Buy, "0.0", "100.0")
+        });
+        let market_order = market_order.unwrap();
+
+        assert!(matches!(result, MatchResult::PartiallyFilledCancelled));
+        assert_eq!(market_order.quantity, Decimal::from_str("100.0").unwrap());
+        assert_eq!(
+            market_order.quantity_filled,
+            Decimal::from_str("40.0").unwrap()
+        );
+        assert_eq!(
+            order_book.fill_ratio(market_order.id),
+            Some(Decimal::from_str("0.4").unwrap())
+        );
+    }
+
+    #[test]
+    fn should_round_excess_precision_when_configured() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            precision: Some(PrecisionConfig {
+                price_dp: 2,
+                size_dp: 1,
+                rounding: PrecisionRounding::MidpointNearestEven,
+            }),
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+
+        let order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Buy,
+                "10.12345",
+                "5.26",
+            ))
+            .unwrap();
+
+        assert_eq!(order.price, Decimal::from_str("10.12").unwrap());
+        assert_eq!(order.quantity, Decimal::from_str("5.3").unwrap());
+        assert_eq!(
+            order_book.best_bid(item_id),
+            Some(Decimal::from_str("10.12").unwrap())
+        );
+    }
+
+    #[test]
+    fn should_reject_excess_precision_by_default() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            precision: Some(PrecisionConfig {
+                price_dp: 2,
+                size_dp: 0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+
+        assert!(matches!(
+            order_book.add_order(limit_order_request(
+                item_id,
+                OrderSide::Buy,
+                "10.12345",
+                "5"
+            )),
+            Err(OrderError::ExcessPrecision { value, decimal_places: 2 })
+                if value == Decimal::from_str("10.12345").unwrap()
+        ));
+        assert!(matches!(
+            order_book.add_order(limit_order_request(item_id, OrderSide::Buy, "10.1", "5.5")),
+            Err(OrderError::ExcessPrecision {
+                decimal_places: 0,
+                ..
+            })
+        ));
+        assert!(
+            order_book
+                .add_order(limit_order_request(item_id, OrderSide::Buy, "10.10", "5.0"))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn should_enumerate_every_item_with_resting_orders() {
+        let mut order_book = OrderBookService::new();
+        let bid_only_item = Uuid::new_v4();
+        let ask_only_item = Uuid::new_v4();
+        let two_sided_item = Uuid::new_v4();
+        let filled_item = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(
+                bid_only_item,
+                OrderSide::Buy,
+                "10.0",
+                "5.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                ask_only_item,
+                OrderSide::Sell,
+                "10.0",
+                "5.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                two_sided_item,
+                OrderSide::Buy,
+                "9.0",
+                "5.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                two_sided_item,
+                OrderSide::Sell,
+                "11.0",
+                "7.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                filled_item,
+                OrderSide::Sell,
+                "10.0",
+                "5.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                filled_item,
+                OrderSide::Buy,
+                "10.0",
+                "5.0",
+            ))
+            .unwrap();
+
+        let mut items: Vec<Uuid> = order_book.items().collect();
+        let mut expected_items = vec![bid_only_item, ask_only_item, two_sided_item];
+        items.sort();
+        expected_items.sort();
+        assert_eq!(items, expected_items);
+
+        let all_stats = order_book.all_stats();
+        assert_eq!(all_stats.len(), 3);
+        assert_eq!(all_stats[&two_sided_item].bid_levels, 1);
+        assert_eq!(
+            all_stats[&two_sided_item].total_ask_qty,
+            Decimal::from_str("7.0").unwrap()
+        );
+        assert!(!all_stats.contains_key(&filled_item));
+    }
+
+    #[test]
+    fn should_return_only_trades_after_cursor() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        for order_side in [OrderSide::Sell, OrderSide::Buy] {
+            order_book
+                .add_order(limit_order_request(item_id, order_side, "10.0", "5.0"))
+                .unwrap();
+        }
+        let cursor = order_book.trades.len();
+        assert!(order_book.trades_since(cursor).is_empty());
+
+        for (order_side, price) in [
+            (OrderSide::Sell, "11.0"),
+            (OrderSide::Sell, "12.0"),
+            (OrderSide::Buy, "12.0"),
+        ] {
+            order_book
+                .add_order(limit_order_request(item_id, order_side, price, "5.0"))
+                .unwrap();
+        }
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "12.0", "5.0"))
+            .unwrap();
+
+        let new_trades = order_book.trades_since(cursor);
+        assert_eq!(new_trades.len(), 2);
+        assert_eq!(new_trades[0].price, Decimal::from_str("11.0").unwrap());
+        assert_eq!(new_trades[1].price, Decimal::from_str("12.0").unwrap());
+        assert_eq!(order_book.trades_since(0).len(), 3);
+        assert!(
+            order_book
+                .trades_since(order_book.trades.len() + 1)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn should_bump_book_sequence_per_item_on_every_change() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let other_item_id = Uuid::new_v4();
+        let mut sequences = vec![order_book.book_sequence(item_id)];
+
+        let sell_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "20.0",
+            ))
+            .unwrap();
+        sequences.push(order_book.book_sequence(item_id));
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+        sequences.push(order_book.book_sequence(item_id));
+        order_book
+            .update_order_quantity(sell_order.id, Decimal::from_str("12.0").unwrap())
+            .unwrap();
+        sequences.push(order_book.book_sequence(item_id));
+        order_book.cancel_order(sell_order.id);
+        sequences.push(order_book.book_sequence(item_id));
+
+        assert_eq!(sequences[0], 0);
+        assert!(sequences.windows(2).all(|pair| pair[0] < pair[1]));
+        assert_eq!(order_book.book_sequence(other_item_id), 0);
+
+        order_book
+            .add_order(limit_order_request(
+                other_item_id,
+                OrderSide::Buy,
+                "10.0",
+                "5.0",
+            ))
+            .unwrap();
+        assert_eq!(order_book.book_sequence(other_item_id), 1);
+        assert_eq!(order_book.book_sequence(item_id), sequences[4]);
+
+        let stop_order = order_book
+            .add_order(CreateOrderRequest {
+                order_type: OrderType::Stop {
+                    trigger: Decimal::from_str("20.0").unwrap(),
+                },
+                price: None,
+                ..limit_order_request(item_id, OrderSide::Buy, "0.0", "5.0")
+            })
+            .unwrap();
+        order_book
+            .update_order_quantity(stop_order.id, Decimal::from_str("4.0").unwrap())
+            .unwrap();
+        assert_eq!(order_book.book_sequence(item_id), sequences[4]);
+
+        let restored_book = OrderBookService::restore(order_book.snapshot());
+        assert_eq!(restored_book.book_sequence(item_id), sequences[4]);
+        assert_eq!(restored_book.book_sequence(other_item_id), 1);
+    }
+
+    #[test]
+    fn should_keep_only_most_recent_trades_under_cap() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            max_trades: Some(3),
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+        let trade_once = |order_book: &mut OrderBookService, price: &str| {
+            for order_side in [OrderSide::Sell, OrderSide::Buy] {
+                order_book
+                    .add_order(limit_order_request(item_id, order_side, price, "1.0"))
+                    .unwrap();
+            }
+        };
+
+        for price in ["10.0", "11.0", "12.0", "13.0"] {
+            trade_once(&mut order_book, price);
+        }
+        let cursor = order_book.trade_cursor();
+        trade_once(&mut order_book, "14.0");
+
+        let prices: Vec<Decimal> = order_book.trades.iter().map(|trade| trade.price).collect();
+        assert_eq!(
+            prices,
+            ["12.0", "13.0", "14.0"].map(|price| Decimal::from_str(price).unwrap())
+        );
+        assert_eq!(cursor, 4);
+        assert_eq!(order_book.trade_cursor(), 5);
+        assert_eq!(order_book.trades_since(cursor).len(), 1);
+        assert_eq!(
+            order_book.trades_since(cursor)[0].price,
+            Decimal::from_str("14.0").unwrap()
+        );
+        assert_eq!(order_book.trades_since(0).len(), 3);
+        assert_eq!(
+            order_book.total_volume(item_id),
+            Decimal::from_str("5.0").unwrap()
+        );
+        assert_eq!(
+            order_book.total_notional(item_id),
+            Decimal::from_str("60.0").unwrap()
+        );
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "15.0", "1.0"))
+            .unwrap();
+        let outcome = order_book
+            .add_order_with_trades(limit_order_request(item_id, OrderSide::Buy, "15.0", "1.0"))
+            .unwrap();
+        assert_eq!(outcome.trades.len(), 1);
+        assert_eq!(outcome.trades[0].price, Decimal::from_str("15.0").unwrap());
+        assert_eq!(order_book.trades.len(), 3);
+    }
+
+    #[test]
+    fn should_keep_positions_and_totals_for_evicted_trades() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            max_trades: Some(1),
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+        let trader = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+        let trader_buy = order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0")
+            })
+            .unwrap();
+        for order_side in [OrderSide::Sell, OrderSide::Buy] {
+            order_book
+                .add_order(limit_order_request(item_id, order_side, "12.0", "1.0"))
+                .unwrap();
+        }
+
+        assert_eq!(order_book.trades.len(), 1);
+        assert_eq!(
+            order_book.position(trader, item_id),
+            Decimal::from_str("5.0").unwrap()
+        );
+        assert_eq!(
+            order_book.average_fill_price(trader_buy.id),
+            Some(Decimal::from_str("10.0").unwrap())
+        );
+        assert_eq!(order_book.stats(item_id).trade_count, 2);
+
+        let mut restored_book = OrderBookService::restore(order_book.snapshot());
+        assert_eq!(restored_book.trade_cursor(), 2);
+        assert_eq!(
+            restored_book.total_volume(item_id),
+            Decimal::from_str("6.0").unwrap()
+        );
+        assert_eq!(
+            restored_book.position(trader, item_id),
+            Decimal::from_str("5.0").unwrap()
+        );
+
+        let reduce_only_sell = restored_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                reduce_only: true,
+                ..limit_order_request(item_id, OrderSide::Sell, "11.0", "5.0")
+            })
+            .unwrap();
+        assert_eq!(reduce_only_sell.quantity, Decimal::from_str("5.0").unwrap());
+    }
+
+    #[test]
+    fn should_match_negative_prices_when_allowed() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            allow_negative_prices: true,
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "-4.0", "5.0"))
+            .unwrap();
+        let sell_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "-5.0", "5.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "-6.0", "5.0"))
+            .unwrap();
+        assert_eq!(
+            order_book.best_ask(item_id),
+            Some(Decimal::from_str("-5.0").unwrap())
+        );
+        assert_eq!(
+            order_book.best_bid(item_id),
+            Some(Decimal::from_str("-6.0").unwrap())
+        );
+
+        let buy_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "-5.0", "5.0"))
+            .unwrap();
+
+        assert!(matches!(buy_order.status, OrderStatus::Closed));
+        assert_eq!(order_book.trades.len(), 1);
+        assert_eq!(order_book.trades[0].sell_order_id, sell_order.id);
+        assert_eq!(
+            order_book.trades[0].price,
+            Decimal::from_str("-5.0").unwrap()
+        );
+        assert!(matches!(
+            order_book.add_order(limit_order_request(item_id, OrderSide::Buy, "-5.0", "0.0")),
+            Err(OrderError::NonPositiveQuantity)
+        ));
+        assert!(matches!(
+            OrderBookService::new().add_order(limit_order_request(
+                item_id,
+                OrderSide::Buy,
+                "-5.0",
+                "5.0"
+            )),
+            Err(OrderError::NegativePrice)
+        ));
+    }
+
+    fn first_sell_matched(order_book: &mut OrderBookService, item_id: Uuid, price: &str) -> Uuid {
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, price, "1.0"))
+            .unwrap();
+        order_book.trades.last().unwrap().sell_order_id
+    }
+
+    #[test]
+    fn should_lose_priority_when_amending_price() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let first_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "11.0",
+                "10.0",
+            ))
+            .unwrap();
+        let second_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "10.0",
+            ))
+            .unwrap();
+
+        let amended_order = order_book
+            .amend_order(
+                first_order.id,
+                Some(Decimal::from_str("10.0").unwrap()),
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(amended_order.price, Decimal::from_str("10.0").unwrap());
+        assert_eq!(
+            first_sell_matched(&mut order_book, item_id, "10.0"),
+            second_order.id
+        );
+    }
+
+    #[test]
+    fn should_lose_priority_when_amending_quantity_up() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let first_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "10.0",
+            ))
+            .unwrap();
+        let second_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "10.0",
+            ))
+            .unwrap();
+
+        let amended_order = order_book
+            .amend_order(
+                first_order.id,
+                None,
+                Some(Decimal::from_str("20.0").unwrap()),
+            )
+            .unwrap();
+
+        assert_eq!(amended_order.quantity, Decimal::from_str("20.0").unwrap());
+        assert_eq!(
+            first_sell_matched(&mut order_book, item_id, "10.0"),
+            second_order.id
+        );
+    }
+
+    #[test]
+    fn should_keep_priority_when_amending_quantity_down() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let first_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "10.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "10.0",
+            ))
+            .unwrap();
+
+        let amended_order = order_book
+            .amend_order(
+                first_order.id,
+                None,
+                Some(Decimal::from_str("5.0").unwrap()),
+            )
+            .unwrap();
+
+        assert_eq!(amended_order.quantity, Decimal::from_str("5.0").unwrap());
+        assert_eq!(
+            first_sell_matched(&mut order_book, item_id, "10.0"),
+            first_order.id
+        );
+    }
+
+    #[test]
+    fn should_match_and_preserve_fills_when_amending() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let buy_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "10.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "9.0", "4.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "10.0",
+            ))
+            .unwrap();
+
+        assert!(matches!(
+            order_book.amend_order(buy_order.id, None, Some(Decimal::from_str("3.0").unwrap())),
+            Err(OrderError::QuantityBelowFilled)
+        ));
+        assert!(matches!(
+            order_book.amend_order(
+                buy_order.id,
+                Some(Decimal::from_str("-1.0").unwrap()),
+                Some(Decimal::from_str("8.0").unwrap())
+            ),
+            Err(OrderError::NegativePrice)
+        ));
+        assert_eq!(
+            order_book.get_order_by_id(buy_order.id).unwrap().quantity,
+            Decimal::from_str("10.0").unwrap()
+        );
+
+        let amended_order = order_book
+            .amend_order(
+                buy_order.id,
+                Some(Decimal::from_str("10.0").unwrap()),
+                Some(Decimal::from_str("8.0").unwrap()),
+            )
+            .unwrap();
+
+        assert!(matches!(amended_order.status, OrderStatus::Closed));
+        assert_eq!(
+            amended_order.quantity_filled,
+            Decimal::from_str("8.0").unwrap()
+        );
+        assert_eq!(order_book.trades.len(), 2);
+        assert!(matches!(
+            order_book.amend_order(buy_order.id, None, Some(Decimal::from_str("9.0").unwrap())),
+            Err(OrderError::OrderNotActive)
+        ));
+    }
+
+    #[test]
+    fn should_work_repriced_stop_limit_at_amended_price_once_triggered() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "10.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "11.0",
+                "20.0",
+            ))
+            .unwrap();
+        let stop_limit_order = order_book
+            .add_order(CreateOrderRequest {
+                order_type: OrderType::StopLimit {
+                    trigger: Decimal::from_str("10.0").unwrap(),
+                    limit: Decimal::from_str("10.0").unwrap(),
+                },
+                ..limit_order_request(item_id, OrderSide::Buy, "0.0", "5.0")
+            })
+            .unwrap();
+
+        let amended_order = order_book
+            .amend_order(
+                stop_limit_order.id,
+                Some(Decimal::from_str("11.0").unwrap()),
+                None,
+            )
+            .unwrap();
+        assert!(matches!(
+            amended_order.order_type,
+            OrderType::StopLimit { limit, .. } if limit == Decimal::from_str("11.0").unwrap()
+        ));
+
+        order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "10.0",
+            ))
+            .unwrap();
+
+        let activated_order = order_book.get_order_by_id(stop_limit_order.id).unwrap();
+        assert!(matches!(activated_order.order_type, OrderType::Limit));
+        assert!(matches!(activated_order.status, OrderStatus::Closed));
+        assert_eq!(activated_order.price, Decimal::from_str("11.0").unwrap());
+        let stop_trade = order_book.trades.last().unwrap();
+        assert_eq!(stop_trade.buy_order_id, stop_limit_order.id);
+        assert_eq!(stop_trade.price, Decimal::from_str("11.0").unwrap());
+    }
+
+    #[test]
+    fn should_construct_usable_book_via_default_and_with_capacity() {
+        let item_id = Uuid::new_v4();
+        for mut order_book in [
+            OrderBookService::default(),
+            OrderBookService::with_capacity(64),
+        ] {
+            order_book
+                .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+                .unwrap();
+            let buy_order = order_book
+                .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+                .unwrap();
+
+            assert!(matches!(buy_order.status, OrderStatus::Closed));
+            assert_eq!(order_book.trades.len(), 1);
+        }
+    }
+
+    #[test]
+    fn should_list_fills_of_order_in_execution_order() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let sell_order = order_book
+            .add_order(limit_order_request(
+                item_id,
+                OrderSide::Sell,
+                "10.0",
+                "30.0",
+            ))
+            .unwrap();
+        for quantity in ["5.0", "10.0", "15.0"] {
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    OrderSide::Buy,
+                    "10.0",
+                    quantity,
+                ))
+                .unwrap();
+        }
+
+        let fills = order_book.fills_of(sell_order.id);
+        let fill_quantities: Vec<Decimal> = fills.iter().map(|fill| fill.quantity).collect();
+        assert_eq!(
+            fill_quantities,
+            vec![
+                Decimal::from_str("5.0").unwrap(),
+                Decimal::from_str("10.0").unwrap(),
+                Decimal::from_str("15.0").unwrap(),
+            ]
+        );
+        let trade_ids: Vec<Uuid> = order_book.trades.iter().map(|trade| trade.id).collect();
+        assert_eq!(
+            fills.iter().map(|fill| fill.trade_id).collect::<Vec<_>>(),
+            trade_ids
+        );
+        assert!(
+            fills
+                .windows(2)
+                .all(|pair| pair[0].timestamp <= pair[1].timestamp)
+        );
+        assert!(order_book.fills_of(Uuid::new_v4()).is_empty());
+    }
+
+    #[test]
+    fn should_estimate_impact_across_levels() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        for (price, quantity) in [("10.0", "5.0"), ("11.0", "5.0"), ("12.0", "10.0")] {
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    OrderSide::Sell,
+                    price,
+                    quantity,
+                ))
+                .unwrap();
+        }
+
+        let partial_walk =
+            order_book.estimate_impact(item_id, OrderSide::Buy, Decimal::from_str("8.0").unwrap());
+        assert_eq!(
+            partial_walk.average_price,
+            Some(Decimal::from_str("10.375").unwrap())
+        );
+        assert_eq!(
+            partial_walk.worst_price,
+            Some(Decimal::from_str("11.0").unwrap())
+        );
+        assert_eq!(partial_walk.unfilled_quantity, Decimal::ZERO);
+
+        let full_walk =
+            order_book.estimate_impact(item_id, OrderSide::Buy, Decimal::from_str("25.0").unwrap());
+        assert_eq!(
+            full_walk.average_price,
+            Some(Decimal::from_str("11.25").unwrap())
+        );
+        assert_eq!(
+            full_walk.worst_price,
+            Some(Decimal::from_str("12.0").unwrap())
+        );
+        assert_eq!(
+            full_walk.quantity_filled,
+            Decimal::from_str("20.0").unwrap()
+        );
+        assert_eq!(
+            full_walk.unfilled_quantity,
+            Decimal::from_str("5.0").unwrap()
+        );
+
+        let empty_side =
+            order_book.estimate_impact(item_id, OrderSide::Sell, Decimal::from_str("3.0").unwrap());
+        assert_eq!(empty_side.average_price, None);
+        assert_eq!(
+            empty_side.unfilled_quantity,
+            Decimal::from_str("3.0").unwrap()
+        );
+        assert!(order_book.trades.is_empty());
+        assert_eq!(
+            order_book.stats(item_id).total_ask_qty,
+            Decimal::from_str("20.0").unwrap()
+        );
+    }
+
+    #[test]
+    fn should_preview_order_without_changing_book() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        for (price, quantity) in [("10.0", "10.0"), ("11.0", "10.0"), ("13.0", "10.0")] {
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    OrderSide::Sell,
+                    price,
+                    quantity,
+                ))
+                .unwrap();
+        }
+        let create_order_request = limit_order_request(item_id, OrderSide::Buy, "11.0", "25.0");
+        let book_before = order_book.clone();
+
+        let preview = order_book.preview(&create_order_request);
+
+        assert!(order_book.structurally_eq(&book_before));
+        assert!(matches!(preview.status, Ok(OrderStatus::PartiallyFilled)));
+        assert_eq!(preview.quantity_filled, Decimal::from_str("20.0").unwrap());
+        assert_eq!(
+            preview.average_price,
+            Some(Decimal::from_str("10.5").unwrap())
+        );
+
+        let outcome = order_book
+            .add_order_with_trades(create_order_request)
+            .unwrap();
+        assert!(matches!(preview.status, Ok(status) if status == outcome.order.status));
+        assert_eq!(preview.quantity_filled, outcome.order.quantity_filled);
+        assert_eq!(
+            preview.fills,
+            outcome
+                .trades
+                .iter()
+                .map(|trade| (trade.price, trade.quantity))
+                .collect::<Vec<_>>()
+        );
+        assert!(matches!(
+            order_book
+                .preview(&limit_order_request(item_id, OrderSide::Buy, "-1.0", "5.0"))
+                .status,
+            Err(OrderError::NegativePrice)
+        ));
+    }
+
+    #[test]
+    fn should_preview_against_item_book_with_user_wide_checks() {
+        let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+            max_open_orders_per_user: Some(2),
+            ..Default::default()
+        });
+        let item_id = Uuid::new_v4();
+        let other_item_id = Uuid::new_v4();
+        let trader = Uuid::new_v4();
+        let idempotency_key = Uuid::new_v4();
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                other_item_id,
+                OrderSide::Sell,
+                "9.0",
+                "50.0",
+            ))
+            .unwrap();
+        let keyed_order = order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                idempotency_key: Some(idempotency_key),
+                ..limit_order_request(other_item_id, OrderSide::Buy, "8.0", "5.0")
+            })
+            .unwrap();
+
+        let preview = order_book.preview(&CreateOrderRequest {
+            user_id: trader,
+            ..limit_order_request(item_id, OrderSide::Buy, "10.0", "20.0")
+        });
+        assert!(matches!(preview.status, Ok(OrderStatus::PartiallyFilled)));
+        assert_eq!(
+            preview.fills,
+            vec![(
+                Decimal::from_str("10.0").unwrap(),
+                Decimal::from_str("5.0").unwrap()
+            )]
+        );
+
+        order_book
+            .add_order(CreateOrderRequest {
+                user_id: trader,
+                ..limit_order_request(other_item_id, OrderSide::Buy, "7.0", "5.0")
+            })
+            .unwrap();
+        assert!(matches!(
+            order_book
+                .preview(&CreateOrderRequest {
+                    user_id: trader,
+                    ..limit_order_request(item_id, OrderSide::Buy, "10.0", "20.0")
+                })
+                .status,
+            Err(OrderError::OpenOrderLimitReached { limit: 2 })
+        ));
+
+        let keyed_preview = order_book.preview(&CreateOrderRequest {
+            user_id: trader,
+            idempotency_key: Some(idempotency_key),
+            ..limit_order_request(other_item_id, OrderSide::Buy, "8.0", "5.0")
+        });
+        assert!(matches!(keyed_preview.status, Ok(OrderStatus::Open)));
+        assert!(keyed_preview.fills.is_empty());
+        assert!(matches!(
+            order_book.get_order_by_id(keyed_order.id).unwrap().status,
+            OrderStatus::Open
+        ));
+    }
+
+    #[test]
+    fn should_price_trades_by_match_price_policy() {
+        for (match_price_policy, expected_price) in [
+            (MatchPricePolicy::MakerPrice, "10.0"),
+            (MatchPricePolicy::Midpoint, "10.5"),
+            (MatchPricePolicy::AggressorPrice, "11.0"),
+        ] {
+            let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+                match_price_policy,
+                ..Default::default()
+            });
+            let item_id = Uuid::new_v4();
+
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    OrderSide::Sell,
+                    "10.0",
+                    "10.0",
+                ))
+                .unwrap();
+            order_book
+                .add_order(limit_order_request(item_id, OrderSide::Buy, "11.0", "5.0"))
+                .unwrap();
+            order_book
+                .add_order(CreateOrderRequest {
+                    order_type: OrderType::Market,
+                    price: None,
+                    ..limit_order_request(item_id, OrderSide::Buy, "0.0", "5.0")
+                })
+                .unwrap();
+
+            assert_eq!(
+                order_book.trades[0].price,
+                Decimal::from_str(expected_price).unwrap()
+            );
+            assert_eq!(
+                order_book.trades[1].price,
+                Decimal::from_str("10.0").unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn should_round_midpoint_price_toward_resting_order() {
+        for (resting_side, resting_price, incoming_price, precision) in [
+            (OrderSide::Sell, "10.00", "10.01", None),
+            (OrderSide::Buy, "10.01", "10.00", None),
+            (
+                OrderSide::Sell,
+                "10.00",
+                "10.01",
+                Some(PrecisionConfig {
+                    price_dp: 2,
+                    size_dp: 1,
+                    rounding: PrecisionRounding::Reject,
+                }),
+            ),
+        ] {
+            let mut order_book = OrderBookService::new_with_config(OrderBookConfig {
+                match_price_policy: MatchPricePolicy::Midpoint,
+                precision,
+                ..Default::default()
+            });
+            let item_id = Uuid::new_v4();
+            if precision.is_none() {
+                order_book.set_tick_size(item_id, Decimal::from_str("0.01").unwrap());
+            }
+            let incoming_side = match resting_side {
+                OrderSide::Buy => OrderSide::Sell,
+                OrderSide::Sell => OrderSide::Buy,
+            };
+
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    resting_side,
+                    resting_price,
+                    "5.0",
+                ))
+                .unwrap();
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    incoming_side,
+                    incoming_price,
+                    "5.0",
+                ))
+                .unwrap();
+
+            assert_eq!(
+                order_book.trades[0].price,
+                Decimal::from_str(resting_price).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn should_cancel_every_order_at_price_level() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+
+        let first_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+        let second_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "10.0", "7.0"))
+            .unwrap();
+        let other_level_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "5.0"))
+            .unwrap();
+
+        let cancelled_order_ids =
+            order_book.cancel_level(item_id, OrderSide::Buy, Decimal::from_str("10.0").unwrap());
+
+        assert_eq!(cancelled_order_ids, vec![first_order.id, second_order.id]);
+        for order_id in cancelled_order_ids {
+            assert!(matches!(
+                order_book.get_order_by_id(order_id).unwrap().status,
+                OrderStatus::Cancelled
+            ));
+        }
+        assert_eq!(
+            order_book.depth(item_id, 5).bids,
+            vec![(
+                Decimal::from_str("9.0").unwrap(),
+                Decimal::from_str("5.0").unwrap()
+            )]
+        );
+        assert!(matches!(
+            order_book
+                .get_order_by_id(other_level_order.id)
+                .unwrap()
+                .status,
+            OrderStatus::Open
+        ));
+        assert!(
+            order_book
+                .cancel_level(item_id, OrderSide::Sell, Decimal::from_str("9.0").unwrap())
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn should_track_cumulative_volume_and_notional_per_item() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let other_item_id = Uuid::new_v4();
+
+        for (price, quantity) in [("10.0", "5.0"), ("10.5", "4.0"), ("11.0", "3.0")] {
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    OrderSide::Sell,
+                    price,
+                    quantity,
+                ))
+                .unwrap();
+        }
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "11.0", "10.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                other_item_id,
+                OrderSide::Sell,
+                "3.0",
+                "2.0",
+            ))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(
+                other_item_id,
+                OrderSide::Buy,
+                "3.0",
+                "2.0",
+            ))
+            .unwrap();
+
+        let item_trades = order_book.trades_for_item(item_id);
+        let expected_volume: Decimal = item_trades.iter().map(|trade| trade.quantity).sum();
+        let expected_notional: Decimal = item_trades
+            .iter()
+            .map(|trade| trade.price * trade.quantity)
+            .sum();
+        assert_eq!(item_trades.len(), 3);
+        assert_eq!(order_book.total_volume(item_id), expected_volume);
+        assert_eq!(order_book.total_notional(item_id), expected_notional);
+        assert_eq!(
+            order_book.total_notional(item_id),
+            Decimal::from_str("103.0").unwrap()
+        );
+        assert_eq!(
+            order_book.total_volume(other_item_id),
+            Decimal::from_str("2.0").unwrap()
+        );
+
+        let restored_order_book = OrderBookService::restore(order_book.snapshot());
+        assert_eq!(
+            restored_order_book.total_notional(item_id),
+            expected_notional
+        );
+        assert_eq!(order_book.total_volume(Uuid::new_v4()), Decimal::ZERO);
+    }
+
+    #[test]
+    fn should_count_resting_orders_across_levels_and_sides() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        assert!(order_book.is_empty());
+        assert_eq!(order_book.resting_count(item_id), 0);
+
+        for (order_side, price) in [
+            (OrderSide::Sell, "10.0"),
+            (OrderSide::Sell, "10.0"),
+            (OrderSide::Sell, "11.0"),
+            (OrderSide::Buy, "9.0"),
+        ] {
+            order_book
+                .add_order(limit_order_request(item_id, order_side, price, "5.0"))
+                .unwrap();
+        }
+        assert!(!order_book.is_empty());
+        assert_eq!(order_book.resting_count(item_id), 4);
+        assert_eq!(order_book.resting_count(Uuid::new_v4()), 0);
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "11.0", "15.0"))
+            .unwrap();
+        assert_eq!(order_book.resting_count(item_id), 1);
+
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "9.0", "5.0"))
+            .unwrap();
+        assert_eq!(order_book.resting_count(item_id), 0);
+        assert!(order_book.is_empty());
+    }
+
+    #[test]
+    fn should_set_expiry_explicitly_for_each_time_in_force() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let before_submission = Utc::now();
+
+        let mut expires_at = |time_in_force| {
+            order_book
+                .add_order(CreateOrderRequest {
+                    time_in_force,
+                    ..limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0")
+                })
+                .unwrap()
+                .expires_at
+        };
+
+        assert_eq!(expires_at(TimeInForce::GTC), None);
+        assert_eq!(expires_at(TimeInForce::FOK), None);
+        assert!(
+            expires_at(TimeInForce::DAY)
+                .is_some_and(|expires_at| expires_at >= before_submission + Duration::days(1))
+        );
+        assert!(
+            expires_at(TimeInForce::IOC).is_some_and(|expires_at| expires_at >= before_submission)
+        );
+        let gtd_expiry = before_submission + Duration::hours(3);
+        assert_eq!(expires_at(TimeInForce::GTD(gtd_expiry)), Some(gtd_expiry));
+        assert!(matches!(TimeInForce::default(), TimeInForce::GTC));
+    }
+
+    #[test]
+    fn should_bucket_trades_into_candles() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let start = DateTime::from_timestamp(1_700_006_400, 0).unwrap();
+        let clock = MockClock::new(start);
+        order_book.set_clock(Arc::new(clock.clone()));
+        let mut trade_at = |offset: Duration, price: &str, quantity: &str| {
+            clock.set(start + offset);
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    OrderSide::Sell,
+                    price,
+                    quantity,
+                ))
+                .unwrap();
+            order_book
+                .add_order(limit_order_request(
+                    item_id,
+                    OrderSide::Buy,
+                    price,
+                    quantity,
+                ))
+                .unwrap();
+        };
+
+        trade_at(Duration::minutes(5), "10.0", "2.0");
+        trade_at(Duration::minutes(20), "12.0", "1.0");
+        trade_at(Duration::minutes(40), "9.0", "3.0");
+        trade_at(Duration::minutes(55), "11.0", "4.0");
+        trade_at(Duration::minutes(130), "13.0", "5.0");
+
+        let candles = order_book.candles(item_id, Duration::hours(1));
+
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[0].start, start);
+        assert_eq!(candles[0].open, Decimal::from_str("10.0").unwrap());
+        assert_eq!(candles[0].high, Decimal::from_str("12.0").unwrap());
+        assert_eq!(candles[0].low, Decimal::from_str("9.0").unwrap());
+        assert_eq!(candles[0].close, Decimal::from_str("11.0").unwrap());
+        assert_eq!(candles[0].volume, Decimal::from_str("10.0").unwrap());
+        assert_eq!(candles[1].start, start + Duration::hours(2));
+        assert_eq!(candles[1].open, Decimal::from_str("13.0").unwrap());
+        assert_eq!(candles[1].close, Decimal::from_str("13.0").unwrap());
+        assert_eq!(candles[1].volume, Decimal::from_str("5.0").unwrap());
+        assert!(
+            order_book
+                .candles(Uuid::new_v4(), Duration::hours(1))
+                .is_empty()
+        );
+        assert!(order_book.candles(item_id, Duration::zero()).is_empty());
+    }
+
+    #[test]
+    fn should_expire_day_order_when_mock_clock_advances() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let start = Utc::now();
+        let clock = MockClock::new(start);
+        order_book.set_clock(Arc::new(clock.clone()));
+
+        let buy_order = order_book
+            .add_order(CreateOrderRequest {
+                time_in_force: TimeInForce::DAY,
+                ..limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0")
+            })
+            .unwrap();
+        assert_eq!(buy_order.expires_at, Some(start + Duration::days(1)));
+
+        clock.advance(Duration::days(2));
+        let sell_order = order_book
+            .add_order(limit_order_request(item_id, OrderSide::Sell, "10.0", "5.0"))
+            .unwrap();
+
+        assert!(matches!(sell_order.status, OrderStatus::Open));
+        assert!(order_book.trades.is_empty());
+        assert_eq!(order_book.expire_orders(clock.now()), vec![buy_order.id]);
+        assert_eq!(order_book.best_bid(item_id), None);
+    }
+
+    #[test]
+    fn should_list_only_orders_expiring_within_window() {
+        let mut order_book = OrderBookService::new();
+        let item_id = Uuid::new_v4();
+        let now = Utc::now();
+        let gtd_order = |hours: i64| CreateOrderRequest {
+            time_in_force: TimeInForce::GTD(now + Duration::hours(hours)),
+            ..limit_order_request(item_id, OrderSide::Buy, "10.0", "5.0")
+        };
+
+        let in_three_hours = order_book.add_order(gtd_order(3)).unwrap();
+        let in_one_hour = order_book.add_order(gtd_order(1)).unwrap();
+        order_book.add_order(gtd_order(10)).unwrap();
+        order_book
+            .add_order(limit_order_request(item_id, OrderSide::Buy, "9.0", "5.0"))
+            .unwrap();
+        let cancelled = order_book.add_order(gtd_order(2)).unwrap();
+        order_book.cancel_order(cancelled.id);
+
+        let expiring: Vec<Uuid> = order_book
+            .expiring_within(Duration::hours(4), now)
+            .iter()
+            .map(|order| order.id)
+            .collect();
+
+        assert_eq!(expiring, vec![in_one_hour.id, in_three_hours.id]);
+        assert!(
+            order_book
+                .expiring_within(Duration::hours(4), now + Duration::hours(5))
+                .is_empty()
+        );
+    }
+
+    #[derive(Debug, Clone)]
+    enum BookOperation {
+        Limit {
+            order_side: OrderSide,
+            price: u32,
+            quantity: u32,
+            time_in_force: TimeInForce,
+        },
+        Market {
+            order_side: OrderSide,
+            quantity: u32,
+        },
+        Cancel {
+            index: usize,
+        },
+        Amend {
+            index: usize,
+            price: u32,
+            quantity: u32,
+        },
+    }
+
+    fn book_operation() -> impl Strategy<Value = BookOperation> {
+        let order_side = prop_oneof![Just(OrderSide::Buy), Just(OrderSide::Sell)];
+        let time_in_force = prop_oneof![
+            Just(TimeInForce::GTC),
+            Just(TimeInForce::IOC),
+            Just(TimeInForce::FOK),
+        ];
+        prop_oneof![
+            3 => (order_side.clone(), 95u32..=105, 1u32..=20, time_in_force).prop_map(
+                |(order_side, price, quantity, time_in_force)| BookOperation::Limit {
+                    order_side,
+                    price,
+                    quantity,
+                    time_in_force,
+                }
+            ),
+            1 => (order_side, 1u32..=20)
+                .prop_map(|(order_side, quantity)| BookOperation::Market { order_side, quantity }),
+            1 => any::<usize>().prop_map(|index| BookOperation::Cancel { index }),
+            1 => (any::<usize>(), 95u32..=105, 1u32..=20).prop_map(|(index, price, quantity)| {
+                BookOperation::Amend {
+                    index,
+                    price,
+                    quantity,
+                }
+            }),
+        ]
+    }
+
+    fn apply_book_operation(
+        order_book: &mut OrderBookService,
+        item_id: Uuid,
+        order_ids: &mut Vec<Uuid>,
+        operation: BookOperation,
+    ) {
+        match operation {
+            BookOperation::Limit {
+                order_side,
+                price,
+                quantity,
+                time_in_force,
+            } => {
+                let create_order_request = CreateOrderRequest {
+                    time_in_force,
+                    ..limit_order_request(
+                        item_id,
+                        order_side,
+                        &price.to_string(),
+                        &quantity.to_string(),
+                    )
+                };
+                if let Ok(order) = order_book.add_order(create_order_request) {
+                    order_ids.push(order.id);
+                }
+            }
+            BookOperation::Market {
+                order_side,
+                quantity,
+            } => {
+                let _ = order_book.add_order(CreateOrderRequest {
+                    item_id,
+                    user_id: Uuid::new_v4(),
+                    order_side,
+                    order_type: OrderType::Market,
+                    quantity: Decimal::from(quantity),
+                    ..Default::default()
+                });
+            }
+            BookOperation::Cancel { index } => {
+                if !order_ids.is_empty() {
+                    order_book.cancel_order(order_ids[index % order_ids.len()]);
+                }
+            }
+            BookOperation::Amend {
+                index,
+                price,
+                quantity,
+            } => {
+                if !order_ids.is_empty() {
+                    let _ = order_book.amend_order(
+                        order_ids[index % order_ids.len()],
+                        Some(Decimal::from(price)),
+                        Some(Decimal::from(quantity)),
+                    );
+                }
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn should_never_leave_inactive_orders_in_the_book(
+            operations in prop::collection::vec(book_operation(), 1..60)
+        ) {
+            let mut order_book = OrderBookService::new();
+            let item_id = Uuid::new_v4();
+            let mut order_ids = Vec::new();
+
+            for operation in operations {
+                apply_book_operation(&mut order_book, item_id, &mut order_ids, operation);
+                order_book.debug_assert_invariants();
+            }
+
+            prop_assert!(!order_book.is_crossed(item_id));
+        }
+
+        #[test]
+        fn should_rebuild_identical_book_from_recorded_commands(
+            operations in prop::collection::vec(book_operation(), 1..60)
+        ) {
+            let config = OrderBookConfig {
+                record_commands: true,
+                self_trade_prevention: SelfTradePrevention::CancelBoth,
+                ..Default::default()
+            };
+            let mut order_book = OrderBookService::new_with_config(config.clone());
+            let item_id = Uuid::new_v4();
+            let mut order_ids = Vec::new();
+
+            for operation in operations {
+                apply_book_operation(&mut order_book, item_id, &mut order_ids, operation);
+            }
+            let replayed_book =
+                OrderBookService::replay_commands(config, order_book.commands().to_vec());
+
+            prop_assert!(replayed_book.structurally_eq(&order_book));
+            prop_assert_eq!(replayed_book.commands().len(), order_book.commands().len());
+        }
     }
 }
@@ -0,0 +1,242 @@
+use crate::components::book::Books;
+use crate::components::clock::Clock;
+use crate::components::config::OrderBookConfig;
+use crate::components::dto::{
+    AuctionResult, BookCommand, BookDepth, BookStats, CreateOrderRequest, Order, OrderEvent,
+    OrderOutcome, OrderSide, Trade,
+};
+use crate::components::errors::OrderError;
+use crate::components::services::OrderBookService;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// An order book for one instrument. Every request is submitted under the
+/// `item_id` fixed at construction, so queries drop the item parameter.
+///
+/// The inner engine stores that one item's price levels directly rather than
+/// in per-item maps, and rejects orders for any other item with
+/// `ItemNotInBook`.
+#[derive(Clone)]
+pub struct SingleItemOrderBook {
+    item_id: Uuid,
+    inner: OrderBookService,
+}
+
+impl SingleItemOrderBook {
+    pub fn new(item_id: Uuid) -> Self {
+        Self::new_with_config(item_id, OrderBookConfig::default())
+    }
+
+    pub fn new_with_config(item_id: Uuid, config: OrderBookConfig) -> Self {
+        SingleItemOrderBook {
+            item_id,
+            inner: OrderBookService::with_books(config, Books::single(item_id)),
+        }
+    }
+
+    pub fn item_id(&self) -> Uuid {
+        self.item_id
+    }
+
+    pub fn inner(&self) -> &OrderBookService {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> OrderBookService {
+        self.inner
+    }
+
+    pub fn set_event_listener(&mut self, f: Box<dyn FnMut(&OrderEvent) + Send + Sync>) {
+        self.inner.set_event_listener(f);
+    }
+
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.inner.set_clock(clock);
+    }
+
+    pub fn on_trade(&mut self, f: impl FnMut(&Trade, &Order, &Order) + Send + Sync + 'static) {
+        self.inner.on_trade(f);
+    }
+
+    pub fn set_tick_size(&mut self, tick_size: Decimal) {
+        self.inner.set_tick_size(self.item_id, tick_size);
+    }
+
+    pub fn set_size_limits(
+        &mut self,
+        min_quantity: Option<Decimal>,
+        max_quantity: Option<Decimal>,
+    ) {
+        self.inner
+            .set_size_limits(self.item_id, min_quantity, max_quantity);
+    }
+
+    pub fn halt(&mut self) {
+        self.inner.halt_item(self.item_id);
+    }
+
+    pub fn resume(&mut self) {
+        self.inner.resume_item(self.item_id);
+    }
+
+    pub fn is_halted(&self) -> bool {
+        self.inner.is_halted(self.item_id)
+    }
+
+    pub fn start_auction(&mut self) {
+        self.inner.start_auction(self.item_id);
+    }
+
+    pub fn is_in_auction(&self) -> bool {
+        self.inner.is_in_auction(self.item_id)
+    }
+
+    pub fn run_auction(&mut self) -> Option<AuctionResult> {
+        self.inner.run_auction(self.item_id)
+    }
+
+    pub fn add_order(
+        &mut self,
+        create_order_request: CreateOrderRequest,
+    ) -> Result<Order, OrderError> {
+        self.inner.add_order(self.for_item(create_order_request))
+    }
+
+    pub fn add_order_with_trades(
+        &mut self,
+        create_order_request: CreateOrderRequest,
+    ) -> Result<OrderOutcome, OrderError> {
+        self.inner
+            .add_order_with_trades(self.for_item(create_order_request))
+    }
+
+    pub fn replace_order(
+        &mut self,
+        order_id: Uuid,
+        create_order_request: CreateOrderRequest,
+    ) -> Result<Order, OrderError> {
+        self.inner
+            .replace_order(order_id, self.for_item(create_order_request))
+    }
+
+    pub fn cancel_order(&mut self, order_id: Uuid) -> bool {
+        self.inner.cancel_order(order_id)
+    }
+
+    pub fn cancel_order_get(&mut self, order_id: Uuid) -> Option<Order> {
+        self.inner.cancel_order_get(order_id)
+    }
+
+    pub fn cancel_all(&mut self) -> Vec<Uuid> {
+        self.inner.cancel_all_for_item(self.item_id)
+    }
+
+    pub fn expire_orders(&mut self, now: DateTime<Utc>) -> Vec<Uuid> {
+        self.inner.expire_orders_for_item(self.item_id, now)
+    }
+
+    pub fn update_order_quantity(
+        &mut self,
+        order_id: Uuid,
+        new_quantity: Decimal,
+    ) -> Result<&Order, OrderError> {
+        self.inner.update_order_quantity(order_id, new_quantity)
+    }
+
+    pub fn reduce_order(&mut self, order_id: Uuid, delta: Decimal) -> Result<Order, OrderError> {
+        self.inner.reduce_order(order_id, delta)
+    }
+
+    pub fn update_order_price(&mut self, order_id: Uuid, new_price: Decimal) -> Option<&Order> {
+        self.inner.update_order_price(order_id, new_price)
+    }
+
+    pub fn get_order_by_id(&self, order_id: Uuid) -> Option<&Order> {
+        self.inner.get_order_by_id(order_id)
+    }
+
+    pub fn commands(&self) -> &[BookCommand] {
+        self.inner.commands()
+    }
+
+    pub fn trades(&self) -> &[Trade] {
+        &self.inner.trades
+    }
+
+    pub fn last_price(&self) -> Option<Decimal> {
+        self.inner.last_price(self.item_id)
+    }
+
+    pub fn vwap(&self, since: Option<DateTime<Utc>>) -> Option<Decimal> {
+        self.inner.vwap(self.item_id, since)
+    }
+
+    pub fn best_bid(&self) -> Option<Decimal> {
+        self.inner.best_bid(self.item_id)
+    }
+
+    pub fn best_ask(&self) -> Option<Decimal> {
+        self.inner.best_ask(self.item_id)
+    }
+
+    pub fn best_level(&self, side: OrderSide) -> Option<(Decimal, Decimal)> {
+        self.inner.best_level(self.item_id, side)
+    }
+
+    pub fn spread(&self) -> Option<Decimal> {
+        self.inner.spread(self.item_id)
+    }
+
+    pub fn mid_price(&self) -> Option<Decimal> {
+        self.inner.mid_price(self.item_id)
+    }
+
+    pub fn spread_bps(&self) -> Option<Decimal> {
+        self.inner.spread_bps(self.item_id)
+    }
+
+    pub fn micro_price(&self) -> Option<Decimal> {
+        self.inner.micro_price(self.item_id)
+    }
+
+    pub fn is_crossed(&self) -> bool {
+        self.inner.is_crossed(self.item_id)
+    }
+
+    pub fn depth(&self, levels: usize) -> BookDepth {
+        self.inner.depth(self.item_id, levels)
+    }
+
+    pub fn stats(&self) -> BookStats {
+        self.inner.stats(self.item_id)
+    }
+
+    pub fn book_checksum(&self, levels: usize) -> u32 {
+        self.inner.book_checksum(self.item_id, levels)
+    }
+
+    pub fn book_sequence(&self) -> u64 {
+        self.inner.book_sequence(self.item_id)
+    }
+
+    pub fn resting_orders(&self, side: OrderSide) -> impl Iterator<Item = &Order> {
+        self.inner.resting_orders(self.item_id, side)
+    }
+
+    pub fn pending_orders(&self) -> Vec<&Order> {
+        self.inner.pending_orders(self.item_id)
+    }
+
+    pub fn level2(&self, side: OrderSide, depth: usize) -> Vec<Order> {
+        self.inner.level2(self.item_id, side, depth)
+    }
+
+    fn for_item(&self, create_order_request: CreateOrderRequest) -> CreateOrderRequest {
+        CreateOrderRequest {
+            item_id: self.item_id,
+            ..create_order_request
+        }
+    }
+}
@@ -0,0 +1,176 @@
+#[cfg(test)]
+mod tests {
+    use crate::components::{
+        dto::{CreateOrderRequest, OrderSide, OrderStatus, OrderType, TimeInForce},
+        errors::OrderError,
+        services::OrderBookService,
+        single_item::SingleItemOrderBook,
+    };
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+    use uuid::Uuid;
+
+    fn limit_order_request(
+        order_side: OrderSide,
+        price: &str,
+        quantity: &str,
+    ) -> CreateOrderRequest {
+        CreateOrderRequest {
+            user_id: Uuid::new_v4(),
+            order_side,
+            order_type: OrderType::Limit,
+            time_in_force: TimeInForce::GTC,
+            price: Some(Decimal::from_str(price).unwrap()),
+            quantity: Decimal::from_str(quantity).unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_submit_every_order_under_the_fixed_item() {
+        let item_id = Uuid::new_v4();
+        let mut order_book = OrderBookService::new_for_item(item_id);
+
+        let order = order_book
+            .add_order(CreateOrderRequest {
+                item_id: Uuid::new_v4(),
+                ..limit_order_request(OrderSide::Buy, "10.0", "5.0")
+            })
+            .unwrap();
+
+        assert_eq!(order_book.item_id(), item_id);
+        assert_eq!(order.item_id, item_id);
+        assert_eq!(
+            order_book.best_bid(),
+            Some(Decimal::from_str("10.0").unwrap())
+        );
+    }
+
+    #[test]
+    fn should_match_buy_order_with_existing_sell_order() {
+        let mut order_book = SingleItemOrderBook::new(Uuid::new_v4());
+
+        let sell_order = order_book
+            .add_order(limit_order_request(OrderSide::Sell, "10.0", "100.0"))
+            .unwrap();
+        let buy_order = order_book
+            .add_order(limit_order_request(OrderSide::Buy, "10.0", "100.0"))
+            .unwrap();
+
+        assert!(matches!(buy_order.status, OrderStatus::Closed));
+        assert!(matches!(
+            order_book.get_order_by_id(sell_order.id).unwrap().status,
+            OrderStatus::Closed
+        ));
+        assert_eq!(order_book.trades().len(), 1);
+        assert_eq!(
+            order_book.last_price(),
+            Some(Decimal::from_str("10.0").unwrap())
+        );
+        assert!(order_book.depth(5).asks.is_empty());
+    }
+
+    #[test]
+    fn should_partially_fill_across_price_levels_in_priority_order() {
+        let mut order_book = SingleItemOrderBook::new(Uuid::new_v4());
+
+        let first_order = order_book
+            .add_order(limit_order_request(OrderSide::Sell, "10.0", "30.0"))
+            .unwrap();
+        let second_order = order_book
+            .add_order(limit_order_request(OrderSide::Sell, "10.0", "30.0"))
+            .unwrap();
+        order_book
+            .add_order(limit_order_request(OrderSide::Sell, "11.0", "30.0"))
+            .unwrap();
+
+        let buy_order = order_book
+            .add_order(limit_order_request(OrderSide::Buy, "10.0", "45.0"))
+            .unwrap();
+
+        assert!(matches!(buy_order.status, OrderStatus::Closed));
+        assert_eq!(order_book.trades()[0].sell_order_id, first_order.id);
+        assert_eq!(order_book.trades()[1].sell_order_id, second_order.id);
+        assert_eq!(
+            order_book.best_level(OrderSide::Sell),
+            Some((
+                Decimal::from_str("10.0").unwrap(),
+                Decimal::from_str("15.0").unwrap()
+            ))
+        );
+        assert!(!order_book.is_crossed());
+    }
+
+    #[test]
+    fn should_not_match_orders_with_incompatible_prices() {
+        let mut order_book = SingleItemOrderBook::new(Uuid::new_v4());
+
+        order_book
+            .add_order(limit_order_request(OrderSide::Sell, "11.0", "10.0"))
+            .unwrap();
+        let buy_order = order_book
+            .add_order(limit_order_request(OrderSide::Buy, "10.0", "10.0"))
+            .unwrap();
+
+        assert!(matches!(buy_order.status, OrderStatus::Open));
+        assert!(order_book.trades().is_empty());
+        assert_eq!(order_book.spread(), Some(Decimal::from_str("1.0").unwrap()));
+        assert_eq!(order_book.stats().open_orders, 2);
+    }
+
+    #[test]
+    fn should_fill_market_order_at_best_available_price() {
+        let mut order_book = SingleItemOrderBook::new(Uuid::new_v4());
+
+        order_book
+            .add_order(limit_order_request(OrderSide::Sell, "10.0", "20.0"))
+            .unwrap();
+        let market_order = order_book
+            .add_order(CreateOrderRequest {
+                order_type: OrderType::Market,
+                price: None,
+                ..limit_order_request(OrderSide::Buy, "10.0", "5.0")
+            })
+            .unwrap();
+
+        assert!(matches!(market_order.status, OrderStatus::Closed));
+        assert_eq!(
+            order_book.trades()[0].price,
+            Decimal::from_str("10.0").unwrap()
+        );
+        assert_eq!(order_book.cancel_all().len(), 1);
+        assert_eq!(order_book.best_ask(), None);
+    }
+
+    #[test]
+    fn should_reject_foreign_items_on_the_inner_engine() {
+        let item_id = Uuid::new_v4();
+        let mut order_book = SingleItemOrderBook::new(item_id);
+
+        let buy_order = order_book
+            .add_order(limit_order_request(OrderSide::Buy, "10.0", "5.0"))
+            .unwrap();
+        assert!(order_book.cancel_order(buy_order.id));
+        assert!(order_book.inner().is_empty());
+
+        let mut inner = order_book.into_inner();
+        let result = inner.add_order(CreateOrderRequest {
+            item_id: Uuid::new_v4(),
+            ..limit_order_request(OrderSide::Buy, "10.0", "5.0")
+        });
+
+        assert!(matches!(result, Err(OrderError::ItemNotInBook)));
+        inner
+            .add_order(CreateOrderRequest {
+                item_id,
+                ..limit_order_request(OrderSide::Sell, "11.0", "5.0")
+            })
+            .unwrap();
+        assert_eq!(
+            inner.best_ask(item_id),
+            Some(Decimal::from_str("11.0").unwrap())
+        );
+        assert_eq!(inner.items().collect::<Vec<_>>(), vec![item_id]);
+        inner.debug_assert_invariants();
+    }
+}
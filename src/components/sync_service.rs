@@ -0,0 +1,81 @@
+use std::sync::{Arc, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+use crate::components::dto::{BookDepth, CreateOrderRequest, Order, OrderOutcome, Trade};
+use crate::components::errors::OrderError;
+use crate::components::services::OrderBookService;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+#[derive(Clone)]
+pub struct SyncOrderBookService {
+    inner: Arc<RwLock<OrderBookService>>,
+}
+
+impl SyncOrderBookService {
+    pub fn new(order_book: OrderBookService) -> Self {
+        SyncOrderBookService {
+            inner: Arc::new(RwLock::new(order_book)),
+        }
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, OrderBookService> {
+        self.inner
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> RwLockWriteGuard<'_, OrderBookService> {
+        self.inner
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn add_order(&self, create_order_request: CreateOrderRequest) -> Result<Order, OrderError> {
+        self.write().add_order(create_order_request)
+    }
+
+    pub fn add_order_with_trades(
+        &self,
+        create_order_request: CreateOrderRequest,
+    ) -> Result<OrderOutcome, OrderError> {
+        self.write().add_order_with_trades(create_order_request)
+    }
+
+    pub fn cancel_order(&self, order_id: Uuid) -> bool {
+        self.write().cancel_order(order_id)
+    }
+
+    pub fn expire_orders(&self, now: DateTime<Utc>) -> Vec<Uuid> {
+        self.write().expire_orders(now)
+    }
+
+    pub fn depth(&self, item_id: Uuid, levels: usize) -> BookDepth {
+        self.read().depth(item_id, levels)
+    }
+
+    pub fn get_order_by_id(&self, order_id: Uuid) -> Option<Order> {
+        self.read().get_order_by_id(order_id).cloned()
+    }
+
+    pub fn trades(&self) -> Vec<Trade> {
+        self.read().trades.clone()
+    }
+
+    pub fn trade_count(&self) -> usize {
+        self.read().trade_cursor()
+    }
+
+    pub fn with_read<R>(&self, f: impl FnOnce(&OrderBookService) -> R) -> R {
+        f(&self.read())
+    }
+
+    pub fn with_write<R>(&self, f: impl FnOnce(&mut OrderBookService) -> R) -> R {
+        f(&mut self.write())
+    }
+}
+
+impl Default for SyncOrderBookService {
+    fn default() -> Self {
+        Self::new(OrderBookService::new())
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+    use crate::components::{
+        dto::{CreateOrderRequest, OrderSide, OrderStatus, OrderType, TimeInForce},
+        sync_service::SyncOrderBookService,
+    };
+    use rust_decimal::Decimal;
+    use std::{str::FromStr, thread};
+    use uuid::Uuid;
+
+    #[test]
+    fn should_add_orders_concurrently_from_several_threads() {
+        let order_book = SyncOrderBookService::default();
+        let item_ids: Vec<Uuid> = (0..4).map(|_| Uuid::new_v4()).collect();
+
+        let handles: Vec<_> = item_ids
+            .iter()
+            .map(|item_id| {
+                let order_book = order_book.clone();
+                let item_id = *item_id;
+                thread::spawn(move || {
+                    for _ in 0..25 {
+                        for order_side in [OrderSide::Sell, OrderSide::Buy] {
+                            order_book
+                                .add_order(CreateOrderRequest {
+                                    item_id,
+                                    user_id: Uuid::new_v4(),
+                                    order_side,
+                                    order_type: OrderType::Limit,
+                                    time_in_force: TimeInForce::GTC,
+                                    price: Some(Decimal::from_str("10.0").unwrap()),
+                                    quantity: Decimal::from_str("1.0").unwrap(),
+                                    ..Default::default()
+                                })
+                                .unwrap();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        assert_eq!(order_book.trade_count(), 100);
+        for item_id in item_ids {
+            let depth = order_book.depth(item_id, 5);
+            assert!(depth.bids.is_empty());
+            assert!(depth.asks.is_empty());
+        }
+    }
+
+    #[test]
+    fn should_return_owned_order_from_read() {
+        let order_book = SyncOrderBookService::default();
+        let order = order_book
+            .add_order(CreateOrderRequest {
+                item_id: Uuid::new_v4(),
+                user_id: Uuid::new_v4(),
+                order_side: OrderSide::Buy,
+                order_type: OrderType::Limit,
+                time_in_force: TimeInForce::GTC,
+                price: Some(Decimal::from_str("10.0").unwrap()),
+                quantity: Decimal::from_str("5.0").unwrap(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert!(order_book.cancel_order(order.id));
+        let fetched_order = order_book.get_order_by_id(order.id).unwrap();
+        assert!(matches!(fetched_order.status, OrderStatus::Cancelled));
+    }
+}
@@ -1,6 +1,18 @@
 mod components;
 
+pub use components::clock::{Clock, MockClock, SystemClock};
+pub use components::config::{
+    FeeSchedule, MatchPricePolicy, OrderBookConfig, PrecisionConfig, PrecisionRounding,
+    SelfTradePrevention, SizeLimits,
+};
 pub use components::dto::{
-    CreateOrderRequest, Order, OrderSide, OrderStatus, OrderType, TimeInForce, Trade,
+    AuctionResult, BookCommand, BookDepth, BookStats, Candle, CreateOrderRequest, Fill,
+    ImpactEstimate, MatchResult, Order, OrderBookSnapshot, OrderEvent, OrderOutcome, OrderSide,
+    OrderStatus, OrderType, PositionState, PreviewResult, TimeInForce, Trade, TradedTotals,
 };
+pub use components::errors::OrderError;
+#[cfg(feature = "serde")]
+pub use components::errors::ReplayError;
 pub use components::services::OrderBookService;
+pub use components::single_item::SingleItemOrderBook;
+pub use components::sync_service::SyncOrderBookService;